@@ -0,0 +1,2567 @@
+//! Procedural macros for [`fire`](https://docs.rs/fire).
+//!
+//! This crate is an implementation detail of `fire`; depend on `fire` and use
+//! `#[fire::main]` instead. Generated code refers to runtime support through
+//! `::fire`.
+
+mod markdown;
+mod style;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::Parser;
+use syn::{
+    parse_macro_input, Attribute, Expr, FnArg, Item, ItemFn, ItemMod, Lit, Meta, Pat, ReturnType,
+    Token, Type,
+};
+
+/// Options given to the attribute itself, as in `#[fire::main(tokio)]`.
+#[derive(Default)]
+struct Settings {
+    tokio: bool,
+    abbrev: bool,
+    /// The configuration file read for option defaults unless `--config`
+    /// names another one.
+    config: Option<String>,
+    /// Asks for every missing required option on a terminal.
+    prompt: bool,
+    /// Adds a `shell` command to a command module that runs commands typed
+    /// line by line.
+    repl: bool,
+}
+
+impl Settings {
+    fn parse(metadata: TokenStream2) -> syn::Result<Self> {
+        let mut settings = Settings::default();
+        let parser = syn::meta::parser(|meta| {
+            if meta.path.is_ident("tokio") {
+                settings.tokio = true;
+            } else if meta.path.is_ident("abbrev") {
+                settings.abbrev = true;
+            } else if meta.path.is_ident("config") {
+                settings.config = Some(meta.value()?.parse::<syn::LitStr>()?.value());
+            } else if meta.path.is_ident("prompt") {
+                settings.prompt = true;
+            } else if meta.path.is_ident("repl") {
+                settings.repl = true;
+            } else {
+                return Err(meta.error(
+                    "unsupported option; expected `tokio`, `abbrev`, `config`, `prompt`, or `repl`",
+                ));
+            }
+            Ok(())
+        });
+        parser.parse2(metadata)?;
+        Ok(settings)
+    }
+}
+
+/// Options given with `#[fire(...)]` on a command function, below
+/// `#[fire::main]` or on a function of a command module.
+#[derive(Default)]
+struct FunctionSettings {
+    /// Takes `@path` arguments as they are instead of reading arguments from
+    /// the file.
+    no_response_files: bool,
+    /// Asks this question before running the command, unless `--yes` is
+    /// given.
+    confirm: Option<syn::LitStr>,
+}
+
+impl FunctionSettings {
+    /// Parses and removes the `#[fire(...)]` attributes of `function`.
+    fn take(function: &mut ItemFn) -> syn::Result<Self> {
+        let mut settings = FunctionSettings::default();
+        for attribute in &function.attrs {
+            if !attribute.path().is_ident("fire") {
+                continue;
+            }
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("no_response_files") {
+                    settings.no_response_files = true;
+                } else if meta.path.is_ident("confirm") {
+                    settings.confirm = Some(meta.value()?.parse()?);
+                } else {
+                    return Err(meta.error(
+                        "unsupported command option; expected `no_response_files` or `confirm`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        function
+            .attrs
+            .retain(|attribute| !attribute.path().is_ident("fire"));
+        Ok(settings)
+    }
+}
+
+/// Options given with `#[fire(...)]` on a parameter.
+#[derive(Default)]
+struct ParameterSettings {
+    /// A repeated option replaces the earlier value instead of being rejected.
+    overrides: bool,
+    /// Parses the raw value instead of `FromStr`.
+    parse_with: Option<Expr>,
+    /// Checks the parsed value before the command runs.
+    validate: Option<Expr>,
+    /// Turns a `bool` flag on unless `--no-<name>` is given.
+    default: bool,
+    /// Counts how often an integer flag is given.
+    count: bool,
+    /// Parameters that may not be given together with this one.
+    conflicts_with: Vec<Ident>,
+    /// Parameters that must be given whenever this one is.
+    requires: Vec<Ident>,
+    /// The group this parameter belongs to.
+    group: Option<GroupSettings>,
+    /// The environment variable read when the option is not given.
+    env: Option<syn::LitStr>,
+    /// Asks for the value on a terminal when a required option is missing.
+    prompt: bool,
+    /// Keeps the value out of error messages and reads it without echo when
+    /// prompting.
+    secret: bool,
+}
+
+/// Membership in a named group, from `group = name` or
+/// `group(name, required, multiple)`. The options of a group are the union of
+/// those given on its members.
+struct GroupSettings {
+    name: Ident,
+    /// One of the members must be given.
+    required: bool,
+    /// Several members may be given together.
+    multiple: bool,
+}
+
+impl ParameterSettings {
+    fn parse(attributes: &[Attribute]) -> syn::Result<Self> {
+        let mut settings = ParameterSettings::default();
+        for attribute in attributes {
+            if attribute.path().is_ident("doc") {
+                continue;
+            }
+            if !attribute.path().is_ident("fire") {
+                return Err(syn::Error::new_spanned(
+                    attribute,
+                    "only documentation comments and #[fire(...)] are supported on parameters",
+                ));
+            }
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("overrides") {
+                    settings.overrides = true;
+                } else if meta.path.is_ident("parse_with") {
+                    settings.parse_with = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("validate") {
+                    settings.validate = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("default") {
+                    settings.default = meta.value()?.parse::<syn::LitBool>()?.value;
+                } else if meta.path.is_ident("count") {
+                    settings.count = true;
+                } else if meta.path.is_ident("conflicts_with") {
+                    settings.conflicts_with.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("requires") {
+                    settings.requires.push(meta.value()?.parse()?);
+                } else if meta.path.is_ident("group") {
+                    settings.group = Some(GroupSettings::parse(&meta)?);
+                } else if meta.path.is_ident("env") {
+                    settings.env = Some(meta.value()?.parse()?);
+                } else if meta.path.is_ident("prompt") {
+                    settings.prompt = true;
+                } else if meta.path.is_ident("secret") {
+                    settings.secret = true;
+                } else {
+                    return Err(meta.error(
+                        "unsupported parameter option; expected `overrides`, `parse_with`, \
+                         `validate`, `default`, `count`, `conflicts_with`, `requires`, `group`, \
+                         `env`, `prompt`, or `secret`",
+                    ));
+                }
+                Ok(())
+            })?;
+        }
+        Ok(settings)
+    }
+}
+
+impl GroupSettings {
+    fn parse(meta: &syn::meta::ParseNestedMeta) -> syn::Result<Self> {
+        if meta.input.peek(Token![=]) {
+            return Ok(GroupSettings {
+                name: meta.value()?.parse()?,
+                required: false,
+                multiple: false,
+            });
+        }
+        let mut name = None;
+        let mut required = false;
+        let mut multiple = false;
+        meta.parse_nested_meta(|option| {
+            if name.is_none() {
+                name = Some(option.path.require_ident()?.clone());
+            } else if option.path.is_ident("required") {
+                required = true;
+            } else if option.path.is_ident("multiple") {
+                multiple = true;
+            } else {
+                return Err(
+                    option.error("unsupported group option; expected `required` or `multiple`")
+                );
+            }
+            Ok(())
+        })?;
+        let name =
+            name.ok_or_else(|| meta.error("expected a group name, as in `group(format)`"))?;
+        Ok(GroupSettings {
+            name,
+            required,
+            multiple,
+        })
+    }
+}
+
+/// A named group of parameters with the options of all its members combined.
+struct Group<'a> {
+    name: String,
+    members: Vec<&'a Argument>,
+    required: bool,
+    multiple: bool,
+}
+
+/// Collects the groups of a command in the order their first member appears.
+fn groups(arguments: &[Argument]) -> Vec<Group<'_>> {
+    let mut groups: Vec<Group<'_>> = Vec::new();
+    for argument in arguments {
+        let Some(settings) = &argument.settings.group else {
+            continue;
+        };
+        let name = settings.name.to_string();
+        let index = match groups.iter().position(|group| group.name == name) {
+            Some(index) => index,
+            None => {
+                groups.push(Group {
+                    name,
+                    members: Vec::new(),
+                    required: false,
+                    multiple: false,
+                });
+                groups.len() - 1
+            }
+        };
+        let group = &mut groups[index];
+        group.members.push(argument);
+        group.required |= settings.required;
+        group.multiple |= settings.multiple;
+    }
+    groups
+}
+
+/// Joins quoted option names as in `'--a', '--b', or '--c'`.
+fn alternatives(names: &[String]) -> String {
+    let quoted: Vec<String> = names.iter().map(|name| format!("'{name}'")).collect();
+    match quoted.as_slice() {
+        [] => String::new(),
+        [single] => single.clone(),
+        [first, second] => format!("{first} or {second}"),
+        [rest @ .., last] => format!("{}, or {last}", rest.join(", ")),
+    }
+}
+
+struct Argument {
+    ident: Ident,
+    ty: Type,
+    cli_name: String,
+    description: String,
+    kind: ArgumentKind,
+    settings: ParameterSettings,
+}
+
+#[derive(Clone, Copy)]
+enum ArgumentKind {
+    Required,
+    Optional,
+    /// `Option<Option<T>>`: `--name` alone, or `--name=value`.
+    OptionalValue,
+    Flag,
+    Repeated,
+    Count,
+}
+
+fn kebab_case(name: &str) -> String {
+    name.replace('_', "-")
+}
+
+fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != wrapper {
+        return None;
+    }
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    match arguments.args.first()? {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    }
+}
+
+/// The element type of a `&[T]` slice.
+fn slice_element(ty: &Type) -> Option<&Type> {
+    let Type::Reference(reference) = ty else {
+        return None;
+    };
+    match &*reference.elem {
+        Type::Slice(slice) => Some(&slice.elem),
+        _ => None,
+    }
+}
+
+/// The type of a single value of the option, without the `Option`, `Vec`, or
+/// slice around it.
+fn value_type(argument: &Argument) -> &Type {
+    let ty = &argument.ty;
+    match argument.kind {
+        ArgumentKind::Optional => inner_type(ty, "Option"),
+        ArgumentKind::OptionalValue => {
+            inner_type(ty, "Option").and_then(|inner| inner_type(inner, "Option"))
+        }
+        ArgumentKind::Repeated => slice_element(ty).or_else(|| inner_type(ty, "Vec")),
+        ArgumentKind::Required | ArgumentKind::Flag | ArgumentKind::Count => None,
+    }
+    .unwrap_or(ty)
+}
+
+/// Whether a flag also accepts `--no-<name>`: one that defaults to on, or an
+/// `Option<bool>` that tells an explicit `false` apart from absence.
+fn is_negatable(argument: &Argument) -> bool {
+    matches!(argument.kind, ArgumentKind::Flag)
+        && (argument.settings.default || !is_bool(&argument.ty))
+}
+
+/// The name of a flag in usage lines and option lists.
+fn flag_name(argument: &Argument) -> String {
+    if is_negatable(argument) {
+        format!("--[no-]{}", argument.cli_name)
+    } else {
+        format!("--{}", argument.cli_name)
+    }
+}
+
+/// The last segment of `ty` when it names one of `names` in `module`, written
+/// with the module path, or bare when `bare` is set.
+fn named_type<'a>(
+    ty: &'a Type,
+    module: &[&str],
+    names: &[&str],
+    bare: bool,
+) -> Option<&'a syn::PathSegment> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let segments: Vec<&syn::PathSegment> = path.path.segments.iter().collect();
+    let (last, prefix) = segments.split_last()?;
+    let in_module = if prefix.is_empty() {
+        bare && path.path.leading_colon.is_none()
+    } else {
+        prefix.len() == module.len()
+            && prefix.iter().zip(module).all(|(segment, name)| {
+                segment.ident == name && segment.arguments.is_none()
+            })
+    };
+    (in_module && names.iter().any(|name| last.ident == name)).then_some(last)
+}
+
+/// The last segment of `ty` when it is one of the types `fire` provides. They
+/// are only recognized through the `fire::` path, so that a type of the same
+/// name defined elsewhere is parsed through its own `FromStr`.
+fn fire_type<'a>(ty: &'a Type, names: &[&str]) -> Option<&'a syn::PathSegment> {
+    named_type(ty, &["fire"], names, false)
+}
+
+/// The key and value types of a `HashMap<K, V>` or `BTreeMap<K, V>`.
+fn map_types(ty: &Type) -> Option<(&Type, &Type)> {
+    let segment = named_type(ty, &["std", "collections"], &["HashMap", "BTreeMap"], true)?;
+    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return None;
+    };
+    let mut types = arguments.args.iter().filter_map(|argument| match argument {
+        syn::GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    });
+    Some((types.next()?, types.next()?))
+}
+
+/// Whether `ty` is `fire::Count`.
+fn is_count(ty: &Type) -> bool {
+    fire_type(ty, &["Count"]).is_some()
+}
+
+/// The short name of a counted flag: its first letter.
+fn short_name(argument: &Argument) -> char {
+    argument
+        .cli_name
+        .chars()
+        .next()
+        .expect("parameter names are not empty")
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
+}
+
+fn is_integer(ty: &Type) -> bool {
+    const INTEGERS: [&str; 12] = [
+        "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    ];
+    matches!(ty, Type::Path(path)
+        if INTEGERS.iter().any(|integer| path.path.is_ident(integer)))
+}
+
+/// Describes the accepted format of the value types provided by `fire`.
+fn value_hint(argument: &Argument) -> Option<&'static str> {
+    if argument.settings.parse_with.is_some() {
+        return None;
+    }
+    let segment = fire_type(value_type(argument), &["Duration", "ByteSize"])?;
+    if segment.ident == "Duration" {
+        Some("(e.g. 30s, 5m, or 1h30m)")
+    } else {
+        Some("(e.g. 512KiB, 64MiB, or 1.5GB)")
+    }
+}
+
+/// Whether `ty` is `fire::Input` or `fire::Output`, which open a file, or the
+/// standard streams for `-`, while parsing.
+fn is_stream(ty: &Type) -> bool {
+    fire_type(ty, &["Input", "Output"]).is_some()
+}
+
+/// Whether `ty` is `PathBuf` or `OsString`, which receive the argument as the
+/// operating system passed it, without requiring UTF-8.
+fn is_os_string(ty: &Type) -> bool {
+    named_type(ty, &["std", "path"], &["PathBuf"], true).is_some()
+        || named_type(ty, &["std", "ffi"], &["OsString"], true).is_some()
+}
+
+/// The name shown for an option's value in usage lines and option lists.
+fn placeholder(argument: &Argument) -> String {
+    if argument.settings.parse_with.is_some() {
+        return argument.cli_name.replace('-', "_").to_uppercase();
+    }
+    if map_types(&argument.ty).is_some() {
+        return "KEY=VALUE".to_string();
+    }
+    match value_type(argument) {
+        Type::Tuple(tuple) if !tuple.elems.is_empty() => vec!["VALUE"; tuple.elems.len()].join(","),
+        ty if is_stream(ty) => "FILE|-".to_string(),
+        _ => argument.cli_name.replace('-', "_").to_uppercase(),
+    }
+}
+
+/// The referenced type of `&Path` or `&OsStr`, which borrow the argument as
+/// the operating system passed it.
+fn borrowed_os_str(ty: &Type) -> Option<&Type> {
+    let Type::Reference(reference) = ty else {
+        return None;
+    };
+    let path = named_type(&reference.elem, &["std", "path"], &["Path"], true);
+    let os_str = named_type(&reference.elem, &["std", "ffi"], &["OsStr"], true);
+    (path.is_some() || os_str.is_some()).then_some(&*reference.elem)
+}
+
+fn is_str_reference(ty: &Type) -> bool {
+    matches!(ty, Type::Reference(reference)
+        if matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")))
+}
+
+/// Collects documentation comments, keeping blank lines between paragraphs and
+/// the indentation that follows the conventional single leading space.
+fn documentation(attributes: &[Attribute]) -> String {
+    attributes
+        .iter()
+        .filter_map(|attribute| {
+            if !attribute.path().is_ident("doc") {
+                return None;
+            }
+            let Meta::NameValue(meta) = &attribute.meta else {
+                return None;
+            };
+            let Expr::Lit(expression) = &meta.value else {
+                return None;
+            };
+            let Lit::Str(text) = &expression.lit else {
+                return None;
+            };
+            Some(text.value())
+        })
+        .flat_map(|text| {
+            text.split('\n')
+                .map(|line| {
+                    line.strip_prefix(' ')
+                        .unwrap_or(line)
+                        .trim_end()
+                        .to_string()
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim_matches('\n')
+        .to_string()
+}
+
+/// Returns the first paragraph of a documentation comment on a single line.
+fn summary(documentation: &str) -> String {
+    documentation
+        .lines()
+        .map(str::trim)
+        .take_while(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn indented(text: &str, indent: &str) -> String {
+    text.lines()
+        .map(|line| {
+            if line.is_empty() {
+                String::new()
+            } else {
+                format!("{indent}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn arguments(function: &mut ItemFn) -> syn::Result<Vec<Argument>> {
+    let arguments = function
+        .sig
+        .inputs
+        .iter_mut()
+        .map(|input| {
+            let FnArg::Typed(input) = input else {
+                return Err(syn::Error::new_spanned(
+                    input,
+                    "methods cannot be CLI commands",
+                ));
+            };
+            let Pat::Ident(pattern) = &*input.pat else {
+                return Err(syn::Error::new_spanned(
+                    &input.pat,
+                    "command parameters must be identifiers",
+                ));
+            };
+            let settings = ParameterSettings::parse(&input.attrs)?;
+
+            let kind = if settings.count || is_count(&input.ty) {
+                ArgumentKind::Count
+            } else if is_bool(&input.ty) || inner_type(&input.ty, "Option").is_some_and(is_bool) {
+                ArgumentKind::Flag
+            } else if inner_type(&input.ty, "Option")
+                .is_some_and(|inner| inner_type(inner, "Option").is_some())
+            {
+                ArgumentKind::OptionalValue
+            } else if inner_type(&input.ty, "Option").is_some() {
+                ArgumentKind::Optional
+            } else if slice_element(&input.ty).is_some()
+                || inner_type(&input.ty, "Vec").is_some()
+                || map_types(&input.ty).is_some()
+            {
+                ArgumentKind::Repeated
+            } else {
+                ArgumentKind::Required
+            };
+
+            if let (ArgumentKind::Repeated | ArgumentKind::Count, true) = (kind, settings.overrides)
+            {
+                return Err(syn::Error::new_spanned(
+                    &input.ty,
+                    "repeated options already accept every occurrence",
+                ));
+            }
+            if settings.count && !is_integer(&input.ty) {
+                return Err(syn::Error::new_spanned(
+                    &input.ty,
+                    "`count` applies to integer parameters",
+                ));
+            }
+            if settings.default && !is_bool(&input.ty) {
+                return Err(syn::Error::new_spanned(
+                    &input.ty,
+                    "`default` applies to `bool` flags",
+                ));
+            }
+            if let (ArgumentKind::Flag | ArgumentKind::Count, true) = (kind, settings.secret) {
+                return Err(syn::Error::new_spanned(
+                    &input.ty,
+                    "`secret` applies to options with a value",
+                ));
+            }
+            if settings.prompt && !matches!(kind, ArgumentKind::Required) {
+                return Err(syn::Error::new_spanned(
+                    &input.ty,
+                    "`prompt` applies to required options",
+                ));
+            }
+            if let (ArgumentKind::Flag | ArgumentKind::Count, Some(function)) = (
+                kind,
+                settings.parse_with.as_ref().or(settings.validate.as_ref()),
+            ) {
+                return Err(syn::Error::new_spanned(
+                    function,
+                    "flags have no value to parse or validate",
+                ));
+            }
+
+            let description = documentation(&input.attrs);
+            input.attrs.clear();
+
+            Ok(Argument {
+                ident: pattern.ident.clone(),
+                ty: (*input.ty).clone(),
+                cli_name: kebab_case(&pattern.ident.to_string()),
+                description,
+                kind,
+                settings,
+            })
+        })
+        .collect::<syn::Result<Vec<Argument>>>()?;
+
+    let mut shorts: Vec<(char, &Argument)> = Vec::new();
+    for argument in &arguments {
+        if !matches!(argument.kind, ArgumentKind::Count) {
+            continue;
+        }
+        let short = short_name(argument);
+        if short == 'h' {
+            return Err(syn::Error::new_spanned(
+                &argument.ident,
+                "counted flags are named by their first letter, and `-h` is reserved for help",
+            ));
+        }
+        if let Some((_, other)) = shorts.iter().find(|(other, _)| *other == short) {
+            return Err(syn::Error::new_spanned(
+                &argument.ident,
+                format!(
+                    "counted flags `{}` and `{}` would both be `-{short}`",
+                    other.ident, argument.ident
+                ),
+            ));
+        }
+        shorts.push((short, argument));
+    }
+
+    for argument in &arguments {
+        let settings = &argument.settings;
+        for other in settings.conflicts_with.iter().chain(&settings.requires) {
+            if other == &argument.ident {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    "a parameter cannot refer to itself",
+                ));
+            }
+            if !arguments.iter().any(|candidate| &candidate.ident == other) {
+                return Err(syn::Error::new_spanned(
+                    other,
+                    format!("no parameter named `{other}`"),
+                ));
+            }
+        }
+        if let (Some(group), ArgumentKind::Required) = (&settings.group, argument.kind) {
+            return Err(syn::Error::new_spanned(
+                &group.name,
+                "group members must be optional; use `Option<T>`, a flag, or a repeated option",
+            ));
+        }
+    }
+    Ok(arguments)
+}
+
+/// Splits a documentation comment into its summary and the paragraphs that
+/// follow it.
+fn details(documentation: &str) -> (String, String) {
+    let rest = match documentation.split_once("\n\n") {
+        Some((_, rest)) => rest.trim_matches('\n').to_string(),
+        None => String::new(),
+    };
+    (summary(documentation), rest)
+}
+
+const COLOR_HELP: &str = "    --color <WHEN>    Color output: auto, always, or never\n";
+/// The command added by `#[fire::main(repl)]`.
+const SHELL: &str = "shell";
+/// Words the shell handles itself, so commands by these names could not be run
+/// from it.
+const SHELL_WORDS: [&str; 4] = ["help", "history", "exit", "quit"];
+/// Shown instead of the value of a `#[fire(secret)]` parameter.
+const REDACTED: &str = "<redacted>";
+const CONFIG_HELP: &str = "    --config <PATH>    Read option defaults from this file\n    \
+    --fire-print-config    Show where option values come from instead of running\n";
+const BATCH_HELP: &str = "    --fire-batch <FILE|->    Run each line of this file as a command\n";
+
+/// Commands get a built-in `--color` option unless they declare their own.
+fn declares_color(function: &ItemFn) -> bool {
+    function.sig.inputs.iter().any(|input| {
+        matches!(input, FnArg::Typed(input)
+            if matches!(&*input.pat, Pat::Ident(pattern) if pattern.ident == "color"))
+    })
+}
+
+fn push_description(help: &mut String, description: &str, long: bool) {
+    let description = markdown::render(description);
+    let description = if long {
+        description
+    } else {
+        summary(&description)
+    };
+    if !description.is_empty() {
+        help.push_str(&description);
+        help.push_str("\n\n");
+    }
+}
+
+fn push_examples(help: &mut String, examples: &str) {
+    if !examples.is_empty() {
+        help.push_str("\n\nExamples:\n");
+        help.push_str(&indented(&markdown::render(examples), "    "));
+    }
+}
+
+/// How a single option is written in the usage line, without the brackets
+/// that mark it optional.
+fn usage(argument: &Argument) -> String {
+    match argument.kind {
+        ArgumentKind::Flag => flag_name(argument),
+        ArgumentKind::Count => format!("-{}", short_name(argument)),
+        ArgumentKind::OptionalValue => {
+            format!("--{}[=<{}>]", argument.cli_name, placeholder(argument))
+        }
+        ArgumentKind::Required | ArgumentKind::Optional | ArgumentKind::Repeated => {
+            format!("--{} <{}>", argument.cli_name, placeholder(argument))
+        }
+    }
+}
+
+/// Builds the help text for one command. Short help (`-h`) only shows the
+/// summary of each documentation comment; long help (`--help`) shows them in
+/// full.
+fn command_help(
+    function: &ItemFn,
+    arguments: &[Argument],
+    command_name: &str,
+    settings: &Settings,
+    function_settings: &FunctionSettings,
+    long: bool,
+) -> String {
+    let mut help = String::new();
+    let (description, examples) = markdown::examples(&documentation(&function.attrs));
+    push_description(&mut help, &description, long);
+
+    help.push_str("Usage: {program}");
+    if !command_name.is_empty() {
+        help.push(' ');
+        help.push_str(command_name);
+    }
+    let groups = groups(arguments);
+    for argument in arguments {
+        let option = match &argument.settings.group {
+            Some(settings) => {
+                let group = groups
+                    .iter()
+                    .find(|group| settings.name == group.name)
+                    .expect("every group has a member");
+                if !std::ptr::eq(group.members[0], argument) {
+                    continue;
+                }
+                let members: Vec<String> =
+                    group.members.iter().map(|member| usage(member)).collect();
+                let members = members.join(" | ");
+                let repeat = if group.multiple { "..." } else { "" };
+                if group.required {
+                    format!(" ({members}){repeat}")
+                } else {
+                    format!(" [{members}]{repeat}")
+                }
+            }
+            None => match argument.kind {
+                ArgumentKind::Required => format!(" {}", usage(argument)),
+                ArgumentKind::Optional | ArgumentKind::OptionalValue | ArgumentKind::Flag => {
+                    format!(" [{}]", usage(argument))
+                }
+                ArgumentKind::Count | ArgumentKind::Repeated => {
+                    format!(" [{}]...", usage(argument))
+                }
+            },
+        };
+        help.push_str(&option);
+    }
+    if function_settings.confirm.is_some() {
+        help.push_str(" [--yes]");
+    }
+    help.push_str("\n\nOptions:\n");
+    for argument in arguments {
+        let option = match argument.kind {
+            ArgumentKind::Flag => format!("    {}", flag_name(argument)),
+            ArgumentKind::Count => {
+                format!("    -{}, --{}", short_name(argument), argument.cli_name)
+            }
+            ArgumentKind::OptionalValue => format!("    {}", usage(argument)),
+            _ => format!("    --{} <{}>", argument.cli_name, placeholder(argument)),
+        };
+        help.push_str(&option);
+        let (mut summary, rest) = details(&markdown::render(&argument.description));
+        if let Some(hint) = value_hint(argument) {
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(hint);
+        }
+        if let Some(env) = &argument.settings.env {
+            if !summary.is_empty() {
+                summary.push(' ');
+            }
+            summary.push_str(&format!("[env: {}]", env.value()));
+        }
+        if !summary.is_empty() {
+            help.push_str("    ");
+            help.push_str(&summary);
+        }
+        help.push('\n');
+        if long && !rest.is_empty() {
+            help.push_str(&indented(&rest, "            "));
+            help.push_str("\n\n");
+        }
+    }
+    if !declares_color(function) {
+        help.push_str(COLOR_HELP);
+    }
+    if settings.config.is_some() {
+        help.push_str(CONFIG_HELP);
+    }
+    if function_settings.confirm.is_some() {
+        help.push_str("    -y, --yes    Run without asking for confirmation\n");
+    }
+    help.push_str("    -h, --help    Print help");
+    if long {
+        push_examples(&mut help, &examples);
+    }
+    align_columns(&help)
+}
+
+fn program_name() -> TokenStream2 {
+    quote! {
+        std::env::args_os()
+            .next()
+            .and_then(|path| {
+                std::path::Path::new(&path)
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| "app".to_string())
+    }
+}
+
+/// Decides at runtime whether output to `stream` is styled. An explicit
+/// `--color` choice wins. Otherwise only output printed by the generated
+/// `main` is styled, following `NO_COLOR` and `CLICOLOR_FORCE`, or else only
+/// on terminals; text returned to other callers of a runner stays plain.
+fn use_color(choice: TokenStream2, stream: TokenStream2) -> TokenStream2 {
+    quote! {
+        match #choice {
+            Some("always") => true,
+            Some("never") => false,
+            _ => {
+                let enabled = |name: &str| {
+                    std::env::var_os(name).is_some_and(|value| !value.is_empty() && value != "0")
+                };
+                if !::fire::__private::results_printed() {
+                    false
+                } else if std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                    false
+                } else if enabled("CLICOLOR_FORCE") {
+                    true
+                } else {
+                    std::io::IsTerminal::is_terminal(&#stream)
+                        && std::env::var_os("TERM").map_or(true, |term| term != "dumb")
+                }
+            }
+        }
+    }
+}
+
+/// Removes the built-in options from `__fire_args` before option parsing:
+/// with `color`, every `--color <WHEN>`, keeping the last choice in
+/// `__fire_color`; with `config`, `--config <PATH>` and `--fire-print-config`.
+/// With `leading`, only options before the first other argument are
+/// considered, which is where the dispatcher of a command module accepts them.
+fn builtin_options(leading: bool, color: bool, config: bool) -> TokenStream2 {
+    let stop = if leading {
+        quote! { break; }
+    } else {
+        quote! { __fire_position += 1; }
+    };
+    let mut declarations = quote! { let mut __fire_color: Option<String> = None; };
+    let mut branches = Vec::new();
+    if color {
+        branches.push(quote! {
+            if __fire_key == "--color" {
+                __fire_args.remove(__fire_position);
+                let value = match __fire_inline_value {
+                    Some(value) => value,
+                    None if __fire_position < __fire_args.len() => {
+                        __fire_args.remove(__fire_position)
+                    }
+                    None => {
+                        return Err(__fire_error("option '--color' requires a value".to_string()));
+                    }
+                };
+                __fire_color = Some(value.to_string_lossy().into_owned());
+            }
+        });
+    } else {
+        declarations = quote! { let __fire_color: Option<String> = None; };
+    }
+    if config {
+        declarations.extend(quote! {
+            let mut __fire_config_path: Option<std::ffi::OsString> = None;
+            let mut __fire_print_config = false;
+        });
+        branches.push(quote! {
+            if __fire_key == "--config" {
+                __fire_args.remove(__fire_position);
+                __fire_config_path = match __fire_inline_value {
+                    Some(value) => Some(value),
+                    None if __fire_position < __fire_args.len() => {
+                        Some(__fire_args.remove(__fire_position))
+                    }
+                    None => {
+                        return Err(__fire_error("option '--config' requires a value".to_string()));
+                    }
+                };
+            }
+        });
+        branches.push(quote! {
+            if __fire_key == "--fire-print-config" && __fire_inline_value.is_none() {
+                __fire_args.remove(__fire_position);
+                __fire_print_config = true;
+            }
+        });
+    }
+    if branches.is_empty() {
+        return declarations;
+    }
+    let validation = if color {
+        quote! {
+            if let Some(value) = __fire_color
+                .as_deref()
+                .filter(|value| !matches!(*value, "auto" | "always" | "never"))
+            {
+                return Err(__fire_error(format!(
+                    "invalid value for '--color': '{}' (expected auto, always, or never)",
+                    value
+                )));
+            }
+        }
+    } else {
+        quote! {}
+    };
+    quote! {
+        #declarations
+        let mut __fire_position = 0usize;
+        while __fire_position < __fire_args.len() {
+            let (__fire_key, __fire_inline_value) =
+                ::fire::__private::split_option(&__fire_args[__fire_position]);
+            #(#branches else)* {
+                #stop
+            }
+        }
+        #validation
+    }
+}
+
+/// Generates an expression that finds the candidate closest to `value`, if
+/// any is close enough to be a likely typo.
+fn suggestion(value: TokenStream2, candidates: &[String]) -> TokenStream2 {
+    quote! {
+        ::fire::__private::suggestion(#value, &[#(#candidates),*])
+    }
+}
+
+/// Generates a `Result` expression that expands `value` to the only candidate
+/// it is a prefix of. Exact matches and values matching no candidate are
+/// returned unchanged; a prefix of several candidates is an ambiguity error.
+fn abbreviation(value: TokenStream2, candidates: &[String], kind: &str) -> TokenStream2 {
+    quote! {{
+        let value: &str = #value;
+        let candidates: &[&'static str] = &[#(#candidates),*];
+        if candidates.contains(&value) {
+            Ok(value)
+        } else {
+            let matches: Vec<&'static str> = candidates
+                .iter()
+                .copied()
+                .filter(|candidate| candidate.starts_with(value))
+                .collect();
+            match matches.as_slice() {
+                [] => Ok(value),
+                [single] => Ok(*single),
+                _ => {
+                    let matches: Vec<String> =
+                        matches.iter().map(|name| format!("'{}'", name)).collect();
+                    Err(format!(
+                        "{} '{}' is ambiguous; it could be {}",
+                        #kind,
+                        value,
+                        matches.join(", ")
+                    ))
+                }
+            }
+        }
+    }}
+}
+
+/// Renders a type the way it is written in source, for messages.
+fn type_name(ty: &Type) -> String {
+    quote!(#ty)
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(" < ", "<")
+        .replace(" <", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+}
+
+/// Generates an expression describing `error` through `Display` when its type
+/// implements it, and `None` otherwise.
+fn error_description(error: TokenStream2) -> TokenStream2 {
+    quote! {{
+        use ::fire::__private::{ViaDisplay as _, ViaFallback as _};
+        (&::fire::__private::Describe(&#error)).describe()
+    }}
+}
+
+/// Generates a `Result<#ty, String>` expression parsing the `&str` in `text`
+/// with `parser`, or else `FromStr`. Failures are passed to `invalid` with the
+/// parser's own message, when its error type can be displayed, and the
+/// expected type.
+fn parsed_text(
+    text: TokenStream2,
+    ty: &Type,
+    parser: Option<&Expr>,
+    invalid: &TokenStream2,
+) -> TokenStream2 {
+    let expected = format!("expected {}", type_name(ty));
+    let description = error_description(quote! { error });
+    let parse = match parser {
+        Some(parser) => quote! { (#parser)(#text) },
+        None if is_str_reference(ty) => quote! { Ok::<_, std::convert::Infallible>(#text) },
+        None if is_integer(ty) => quote! { ::fire::__private::parse_integer::<#ty>(#text) },
+        None => quote! { #text.parse::<#ty>() },
+    };
+    if parser.is_none() && is_stream(ty) {
+        // The type is evident from the usage line, and the open error says
+        // what went wrong.
+        return quote! { #parse.map_err(|error| (#invalid)(error.to_string())) };
+    }
+    quote! {
+        #parse.map_err(|error| {
+            (#invalid)(match #description {
+                Some(message) if !message.is_empty() => format!("{}; {}", message, #expected),
+                _ => #expected.to_string(),
+            })
+        })
+    }
+}
+
+/// Generates a `Result<#ty, String>` expression splitting the `&str` in `text`
+/// into the components of a tuple and parsing each of them. Map entries are
+/// split at their first `=`, and other tuples at every comma.
+fn parsed_tuple(
+    text: TokenStream2,
+    tuple: &syn::TypeTuple,
+    argument: &Argument,
+    invalid: &TokenStream2,
+) -> TokenStream2 {
+    let count = tuple.elems.len();
+    let (split, shape) = if map_types(&argument.ty).is_some() {
+        (
+            quote! { #text.splitn(2, '=') },
+            "expected KEY=VALUE".to_string(),
+        )
+    } else {
+        (
+            quote! { #text.split(',') },
+            format!("expected {count} comma-separated values"),
+        )
+    };
+    let names: Vec<Ident> = (0..count)
+        .map(|index| format_ident!("part{}", index))
+        .collect();
+    let mut parsed = quote! { Ok((#(#names,)*)) };
+    for (index, element) in tuple.elems.iter().enumerate().rev() {
+        let component = parsed_text(quote! { parts[#index] }, element, None, invalid);
+        let name = &names[index];
+        parsed = quote! { #component.and_then(|#name| #parsed) };
+    }
+    quote! {{
+        let parts: Vec<&str> = #split.collect();
+        if parts.len() == #count {
+            #parsed
+        } else {
+            Err((#invalid)(#shape.to_string()))
+        }
+    }}
+}
+
+/// Generates a `Result<#ty, String>` expression converting the `OsString`
+/// storage behind `value`. `PathBuf` and `OsString` take it as is; other types
+/// require UTF-8 and are parsed with `FromStr` or the parameter's `parse_with`
+/// function, tuples component by component. The result then goes through its
+/// `validate` function.
+fn parsed_value(value: TokenStream2, ty: &Type, argument: &Argument) -> TokenStream2 {
+    let cli_name = &argument.cli_name;
+    let invalid = if argument.settings.secret {
+        // Parsers may quote the value, or a part of it, in their own message,
+        // so only the expected type is shown.
+        let expected = format!("expected {}", type_name(ty));
+        quote! {
+            |_detail: String| {
+                format!("invalid value for '--{}': {} ({})", #cli_name, #REDACTED, #expected)
+            }
+        }
+    } else {
+        quote! {
+            |detail: String| format!(
+                "invalid value for '--{}': '{}' ({})",
+                #cli_name,
+                #value.to_string_lossy(),
+                detail
+            )
+        }
+    };
+    let parser = argument.settings.parse_with.as_ref();
+    let borrowed = borrowed_os_str(ty).filter(|_| parser.is_none());
+    let mut parsed = if let Some(referenced) = borrowed {
+        quote! { Ok::<#ty, String>(<#referenced>::new(#value)) }
+    } else if parser.is_none() && is_os_string(ty) {
+        quote! { Ok::<#ty, String>(<#ty>::from(#value.clone())) }
+    } else {
+        let parsed = match ty {
+            Type::Tuple(tuple) if parser.is_none() && !tuple.elems.is_empty() => {
+                parsed_tuple(quote! { text }, tuple, argument, &invalid)
+            }
+            _ => parsed_text(quote! { text }, ty, parser, &invalid),
+        };
+        let not_unicode = format!("not valid UTF-8; expected {}", type_name(ty));
+        quote! {
+            match #value.to_str() {
+                Some(text) => #parsed,
+                None => Err((#invalid)(#not_unicode.to_string())),
+            }
+        }
+    };
+    if let Some(validator) = &argument.settings.validate {
+        let rejected = format!(
+            "rejected by {}",
+            quote!(#validator).to_string().replace(" :: ", "::")
+        );
+        parsed = quote! {
+            #parsed.and_then(|parsed: #ty| {
+                match ::fire::__private::Validation::problem((#validator)(&parsed)) {
+                    None => Ok(parsed),
+                    Some(message) if message.is_empty() => Err((#invalid)(#rejected.to_string())),
+                    Some(message) => Err((#invalid)(message)),
+                }
+            })
+        };
+    }
+    parsed
+}
+
+fn command_runner(
+    function: &mut ItemFn,
+    runner_name: &Ident,
+    visibility: TokenStream2,
+    command_name: &str,
+    settings: &Settings,
+) -> syn::Result<TokenStream2> {
+    if function.sig.asyncness.is_some() && !settings.tokio {
+        return Err(syn::Error::new_spanned(
+            function.sig.asyncness,
+            "async commands require a runtime; use #[fire::main(tokio)]",
+        ));
+    }
+    if !function.sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &function.sig.generics,
+            "generic commands are not supported",
+        ));
+    }
+
+    let function_settings = FunctionSettings::take(function)?;
+    let arguments = arguments(function)?;
+    if let Some(argument) = arguments
+        .iter()
+        .find(|argument| settings.config.is_some() && argument.cli_name == "config")
+    {
+        return Err(syn::Error::new_spanned(
+            &argument.ident,
+            "`config` is the built-in --config option of #[fire::main(config = ...)]",
+        ));
+    }
+    if let Some(argument) = arguments.iter().find(|argument| {
+        function_settings.confirm.is_some()
+            && (argument.cli_name == "yes"
+                || matches!(argument.kind, ArgumentKind::Count) && short_name(argument) == 'y')
+    }) {
+        return Err(syn::Error::new_spanned(
+            &argument.ident,
+            "`--yes` and `-y` are built-in options of commands with `confirm`",
+        ));
+    }
+    let function_name = &function.sig.ident;
+    let help = command_help(
+        function,
+        &arguments,
+        command_name,
+        settings,
+        &function_settings,
+        false,
+    );
+    let long_help = command_help(
+        function,
+        &arguments,
+        command_name,
+        settings,
+        &function_settings,
+        true,
+    );
+    let usage = help
+        .lines()
+        .find(|line| line.starts_with("Usage:"))
+        .expect("command help always contains usage")
+        .to_string();
+    let styled_help = style::help(&help);
+    let styled_long_help = style::help(&long_help);
+    let program_name = program_name();
+    let color = !declares_color(function);
+    let config = settings.config.is_some();
+    let arguments_binding = if color || config {
+        quote! { let mut __fire_args: Vec<std::ffi::OsString> }
+    } else {
+        quote! { let __fire_args: Vec<std::ffi::OsString> }
+    };
+    let builtin_setup = builtin_options(false, color, config);
+    let collected_arguments = if function_settings.no_response_files {
+        quote! { input.into_iter().map(Into::into).collect() }
+    } else {
+        quote! {
+            ::fire::__private::expand_response_files(input.into_iter().map(Into::into).collect())
+                .map_err(__fire_error)?
+        }
+    };
+    let styled = use_color(
+        quote! { __fire_color.as_deref() },
+        quote! { std::io::stdout() },
+    );
+    let mut user_options: Vec<String> = Vec::new();
+    for argument in &arguments {
+        user_options.push(format!("--{}", argument.cli_name));
+        if is_negatable(argument) {
+            user_options.push(format!("--no-{}", argument.cli_name));
+        }
+    }
+    let mut option_names = user_options.clone();
+    if !declares_color(function) {
+        option_names.push("--color".to_string());
+    }
+    if settings.config.is_some() {
+        option_names.push("--config".to_string());
+    }
+    if function_settings.confirm.is_some() {
+        option_names.push("--yes".to_string());
+    }
+    option_names.push("--help".to_string());
+    let option_suggestion = suggestion(quote! { __fire_key }, &option_names);
+    let resolve_option = if settings.abbrev {
+        let resolved = abbreviation(quote! { __fire_key }, &user_options, "option");
+        quote! {
+            let __fire_key: &str = if __fire_key.len() > 2 && __fire_key.starts_with("--") {
+                match #resolved {
+                    Ok(key) => key,
+                    Err(problem) => {
+                        __fire_problems.push(problem);
+                        break 'argument;
+                    }
+                }
+            } else {
+                __fire_key
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    let storage = arguments.iter().map(|argument| {
+        let storage_name = format_ident!("__fire_value_{}", argument.ident);
+        let occurrence_name = format_ident!("__fire_occurrence_{}", argument.ident);
+        let storage_type = match argument.kind {
+            ArgumentKind::Repeated => {
+                return quote! { let mut #storage_name: Vec<std::ffi::OsString> = Vec::new(); };
+            }
+            ArgumentKind::Count => return quote! { let mut #storage_name: u8 = 0; },
+            ArgumentKind::Flag => quote! { bool },
+            ArgumentKind::Required | ArgumentKind::Optional => quote! { std::ffi::OsString },
+            ArgumentKind::OptionalValue => quote! { Option<std::ffi::OsString> },
+        };
+        let occurrence = if argument.settings.overrides {
+            quote! {}
+        } else {
+            quote! { let mut #occurrence_name: Option<String> = None; }
+        };
+        quote! {
+            let mut #storage_name: Option<#storage_type> = None;
+            #occurrence
+        }
+    });
+
+    let option_matches = arguments.iter().map(|argument| {
+        let storage_name = format_ident!("__fire_value_{}", argument.ident);
+        let occurrence_name = format_ident!("__fire_occurrence_{}", argument.ident);
+        let cli_name = &argument.cli_name;
+        let repeated = |occurrence: TokenStream2| {
+            if argument.settings.overrides {
+                return quote! {};
+            }
+            quote! {
+                let occurrence = #occurrence;
+                if let Some(previous) = &#occurrence_name {
+                    __fire_problems.push(format!(
+                        "option '--{}' was given more than once: '{}' and '{}'",
+                        #cli_name, previous, occurrence
+                    ));
+                    break 'argument;
+                }
+                #occurrence_name = Some(occurrence);
+            }
+        };
+        let (flag_repeated, option_repeated) = if argument.settings.secret {
+            (
+                repeated(quote! {
+                    match __fire_inline_value {
+                        Some(_) => format!("{}={}", __fire_key, #REDACTED),
+                        None => __fire_key.to_string(),
+                    }
+                }),
+                repeated(quote! {
+                    match __fire_inline_value {
+                        Some(_) => format!("{}={}", __fire_key, #REDACTED),
+                        None => format!("{} {}", __fire_key, #REDACTED),
+                    }
+                }),
+            )
+        } else {
+            (
+                repeated(quote! { __fire_raw.to_string_lossy().into_owned() }),
+                repeated(quote! {
+                    match __fire_inline_value {
+                        Some(_) => __fire_raw.to_string_lossy().into_owned(),
+                        None => {
+                            format!("{} {}", __fire_raw.to_string_lossy(), value.to_string_lossy())
+                        }
+                    }
+                }),
+            )
+        };
+        match argument.kind {
+            ArgumentKind::OptionalValue => quote! {
+                if __fire_key == concat!("--", #cli_name) {
+                    __fire_matched = true;
+                    // Only `--name=value` gives a value, so the next argument
+                    // is never taken.
+                    #flag_repeated
+                    #storage_name = Some(__fire_inline_value.clone());
+                }
+            },
+            ArgumentKind::Count => quote! {
+                if __fire_key == concat!("--", #cli_name) {
+                    __fire_matched = true;
+                    if __fire_inline_value.is_some() {
+                        __fire_problems.push(format!("flag '--{}' does not take a value", #cli_name));
+                        break 'argument;
+                    }
+                    #storage_name = #storage_name.saturating_add(1);
+                }
+            },
+            ArgumentKind::Flag => {
+                let negated = if is_negatable(argument) {
+                    quote! {
+                        if __fire_key == concat!("--no-", #cli_name) {
+                            __fire_matched = true;
+                            if __fire_inline_value.is_some() {
+                                __fire_problems
+                                    .push(format!("flag '--no-{}' does not take a value", #cli_name));
+                                break 'argument;
+                            }
+                            #flag_repeated
+                            #storage_name = Some(false);
+                        }
+                    }
+                } else {
+                    quote! {}
+                };
+                quote! {
+                    if __fire_key == concat!("--", #cli_name) {
+                        __fire_matched = true;
+                        let value = match &__fire_inline_value {
+                            None => true,
+                            Some(value) if value == "true" => true,
+                            Some(value) if value == "false" => false,
+                            Some(value) => {
+                                __fire_problems.push(format!(
+                                    "invalid value for '--{}': '{}' (expected true or false)",
+                                    #cli_name,
+                                    value.to_string_lossy()
+                                ));
+                                break 'argument;
+                            }
+                        };
+                        #flag_repeated
+                        #storage_name = Some(value);
+                    }
+                    #negated
+                }
+            }
+            ArgumentKind::Required | ArgumentKind::Optional | ArgumentKind::Repeated => {
+                let store = if let ArgumentKind::Repeated = argument.kind {
+                    quote! { #storage_name.push(value); }
+                } else {
+                    quote! {
+                        #option_repeated
+                        #storage_name = Some(value);
+                    }
+                };
+                quote! {
+                    if __fire_key == concat!("--", #cli_name) {
+                        __fire_matched = true;
+                        let value = match &__fire_inline_value {
+                            Some(value) => value.clone(),
+                            None => match __fire_args.get(__fire_index + 1) {
+                                Some(value)
+                                    if !value.as_encoded_bytes().starts_with(b"--")
+                                        && value != "-h" =>
+                                {
+                                    __fire_index += 1;
+                                    value.clone()
+                                }
+                                _ => {
+                                    __fire_problems.push(format!(
+                                        "option '--{}' requires a value",
+                                        #cli_name
+                                    ));
+                                    __fire_without_value.push(#cli_name);
+                                    break 'argument;
+                                }
+                            },
+                        };
+                        #store
+                    }
+                }
+            }
+        }
+    });
+
+    // Commands that ask for confirmation accept `--yes` and `-y` instead. The
+    // question comes before any value is converted, since converting may open
+    // files, and only when the command line is otherwise complete.
+    let required_given = arguments
+        .iter()
+        .filter(|argument| matches!(argument.kind, ArgumentKind::Required))
+        .map(|argument| {
+            let storage_name = format_ident!("__fire_value_{}", argument.ident);
+            quote! { #storage_name.is_some() }
+        });
+    let (yes_storage, yes_match, confirmation) = match &function_settings.confirm {
+        Some(question) => (
+            quote! { let mut __fire_yes = false; },
+            quote! {
+                if __fire_key == "--yes" || __fire_key == "-y" {
+                    __fire_matched = true;
+                    if __fire_inline_value.is_some() {
+                        __fire_problems
+                            .push(format!("flag '{}' does not take a value", __fire_key));
+                        break 'argument;
+                    }
+                    __fire_yes = true;
+                }
+            },
+            quote! {
+                if !__fire_yes && __fire_problems.is_empty() #(&& #required_given)* {
+                    if !::fire::__private::interactive() {
+                        return Err(__fire_error(
+                            "this command asks for confirmation; \
+                             pass '--yes' to run it without a terminal"
+                                .to_string(),
+                        ));
+                    }
+                    if !::fire::__private::confirm(#question) {
+                        return Err("cancelled".to_string());
+                    }
+                }
+            },
+        ),
+        None => (quote! {}, quote! {}, quote! {}),
+    };
+
+    // Counted flags also have a short name, and several can be combined as in
+    // `-vvq`. A cluster is only taken apart when every letter is known.
+    let counts: Vec<&Argument> = arguments
+        .iter()
+        .filter(|argument| matches!(argument.kind, ArgumentKind::Count))
+        .collect();
+    let short_matches = if counts.is_empty() {
+        quote! {}
+    } else {
+        let letters: Vec<char> = counts.iter().map(|argument| short_name(argument)).collect();
+        let storage_names = counts
+            .iter()
+            .map(|argument| format_ident!("__fire_value_{}", argument.ident));
+        quote! {
+            if !__fire_matched && __fire_inline_value.is_none() {
+                if let Some(letters) = __fire_key
+                    .strip_prefix('-')
+                    .filter(|letters| !letters.is_empty() && !letters.starts_with('-'))
+                    .filter(|letters| letters.chars().all(|letter| matches!(letter, #(#letters)|*)))
+                {
+                    __fire_matched = true;
+                    for letter in letters.chars() {
+                        match letter {
+                            #(#letters => #storage_names = #storage_names.saturating_add(1),)*
+                            _ => unreachable!("letters are checked above"),
+                        }
+                    }
+                }
+            }
+        }
+    };
+
+    // Whether a value was stored for the parameter, including an explicit
+    // `false` for a flag.
+    let has_value = |argument: &Argument| {
+        let storage_name = format_ident!("__fire_value_{}", argument.ident);
+        match argument.kind {
+            ArgumentKind::Count => quote! { (#storage_name > 0) },
+            ArgumentKind::Repeated => quote! { !#storage_name.is_empty() },
+            _ => quote! { #storage_name.is_some() },
+        }
+    };
+    // Relations between parameters are checked on what was given, before any
+    // value is converted. A flag turned off with `--no-name` or `--name=false`
+    // counts as not given.
+    let given = |argument: &Argument| {
+        let storage_name = format_ident!("__fire_value_{}", argument.ident);
+        match argument.kind {
+            ArgumentKind::Flag => quote! { (#storage_name == Some(true)) },
+            _ => has_value(argument),
+        }
+    };
+    // Values from the environment or a configuration file are only defaults:
+    // one that conflicts with an option given on the command line is dropped,
+    // and one that requires another option is not checked.
+    let has_fallback = |argument: &Argument| config || argument.settings.env.is_some();
+    let from_fallback = |argument: &Argument| {
+        if has_fallback(argument) {
+            let fallback_name = format_ident!("__fire_fallback_{}", argument.ident);
+            quote! { #fallback_name }
+        } else {
+            quote! { false }
+        }
+    };
+    let from_command_line = |argument: &Argument| {
+        let (given, from_fallback) = (given(argument), from_fallback(argument));
+        quote! { (#given && !#from_fallback) }
+    };
+    let drop_fallback = |argument: &Argument| {
+        let storage_name = format_ident!("__fire_value_{}", argument.ident);
+        let fallback_name = format_ident!("__fire_fallback_{}", argument.ident);
+        let cli_name = &argument.cli_name;
+        let reset = match argument.kind {
+            ArgumentKind::Repeated => quote! { #storage_name.clear(); },
+            ArgumentKind::Count => quote! { #storage_name = 0; },
+            _ => quote! { #storage_name = None; },
+        };
+        let forget = if config {
+            quote! { __fire_sources.retain(|(name, _)| *name != #cli_name); }
+        } else {
+            quote! {}
+        };
+        quote! {
+            #reset
+            #fallback_name = false;
+            #forget
+        }
+    };
+    let named = |ident: &Ident| {
+        arguments
+            .iter()
+            .find(|argument| &argument.ident == ident)
+            .expect("parameter references are checked in `arguments`")
+    };
+    let mut relations = Vec::new();
+    let mut conflicts: Vec<(String, String)> = Vec::new();
+    for argument in &arguments {
+        for other in &argument.settings.conflicts_with {
+            let other = named(other);
+            let mut pair = [argument.cli_name.clone(), other.cli_name.clone()];
+            pair.sort();
+            let [first, second] = pair;
+            if conflicts.contains(&(first.clone(), second.clone())) {
+                continue;
+            }
+            conflicts.push((first, second));
+            let message = format!(
+                "option '--{}' cannot be used with '--{}'",
+                argument.cli_name, other.cli_name
+            );
+            let (left, right) = (given(argument), given(other));
+            let (left_fallback, right_fallback) = (from_fallback(argument), from_fallback(other));
+            let drop_left = if has_fallback(argument) {
+                drop_fallback(argument)
+            } else {
+                quote! {}
+            };
+            let drop_right = if has_fallback(other) {
+                drop_fallback(other)
+            } else {
+                quote! {}
+            };
+            relations.push(quote! {
+                if #left && #right {
+                    if #left_fallback && !#right_fallback {
+                        #drop_left
+                    } else if #right_fallback && !#left_fallback {
+                        #drop_right
+                    } else {
+                        __fire_problems.push(#message.to_string());
+                    }
+                }
+            });
+        }
+        for other in &argument.settings.requires {
+            let other = named(other);
+            let message = format!(
+                "option '--{}' requires '--{}'",
+                argument.cli_name, other.cli_name
+            );
+            let (left, right) = (from_command_line(argument), given(other));
+            relations.push(quote! {
+                if #left && !#right {
+                    __fire_problems.push(#message.to_string());
+                }
+            });
+        }
+    }
+    for group in groups(&arguments) {
+        if group.multiple && !group.required {
+            // Only shown together in the usage line.
+            continue;
+        }
+        let names: Vec<String> = group
+            .members
+            .iter()
+            .map(|member| format!("--{}", member.cli_name))
+            .collect();
+        let presence = group.members.iter().map(|member| given(member));
+        let exclusive = if group.multiple {
+            quote! {}
+        } else {
+            let from_command_line = group.members.iter().map(|member| from_command_line(member));
+            let drops = group
+                .members
+                .iter()
+                .filter(|member| has_fallback(member))
+                .map(|member| {
+                    let fallback_name = format_ident!("__fire_fallback_{}", member.ident);
+                    let drop = drop_fallback(member);
+                    quote! {
+                        if #fallback_name {
+                            #drop
+                        }
+                    }
+                });
+            quote! {
+                if #(#from_command_line)||* {
+                    #(#drops)*
+                }
+            }
+        };
+        let conflict = if group.multiple {
+            quote! {}
+        } else {
+            quote! {
+                if let [first, second, ..] = given.as_slice() {
+                    __fire_problems
+                        .push(format!("option '{}' cannot be used with '{}'", first, second));
+                }
+            }
+        };
+        let required = if group.required {
+            let message = format!("one of {} is required", alternatives(&names));
+            quote! {
+                if given.is_empty() {
+                    __fire_problems.push(#message.to_string());
+                }
+            }
+        } else {
+            quote! {}
+        };
+        relations.push(quote! {
+            {
+                #exclusive
+                let given: Vec<&str> = [#((#presence, #names)),*]
+                    .into_iter()
+                    .filter_map(|(given, name)| given.then_some(name))
+                    .collect();
+                #conflict
+                #required
+            }
+        });
+    }
+
+    // Options that were not given are looked up in the environment and then
+    // in the configuration file, and parsed like values given on the command
+    // line.
+    let fallbacks = arguments
+        .iter()
+        .filter(|argument| config || argument.settings.env.is_some())
+        .map(|argument| {
+            let storage_name = format_ident!("__fire_value_{}", argument.ident);
+            let cli_name = &argument.cli_name;
+            let env = match &argument.settings.env {
+                Some(name) => quote! { Some(#name) },
+                None => quote! { None },
+            };
+            let (method, stored) = match argument.kind {
+                ArgumentKind::Required | ArgumentKind::Optional => {
+                    (quote! { single }, quote! { Some(value) })
+                }
+                ArgumentKind::OptionalValue => (quote! { optional_value }, quote! { value }),
+                ArgumentKind::Flag => (quote! { flag }, quote! { Some(value) }),
+                ArgumentKind::Count => (quote! { count }, quote! { value }),
+                ArgumentKind::Repeated => (quote! { list }, quote! { value }),
+            };
+            let record = if config {
+                quote! { __fire_sources.push((#cli_name, fallback.source)); }
+            } else {
+                quote! {}
+            };
+            let has_value = has_value(argument);
+            let fallback_name = format_ident!("__fire_fallback_{}", argument.ident);
+            quote! {
+                let mut #fallback_name = false;
+                if !#has_value {
+                    if let Some(fallback) = ::fire::__private::fallback(
+                        #env,
+                        __fire_config.as_ref(),
+                        #command_name,
+                        #cli_name,
+                    ) {
+                        match fallback.#method(#cli_name) {
+                            Ok(value) => {
+                                #storage_name = #stored;
+                                #fallback_name = true;
+                                #record
+                            }
+                            Err(problem) => __fire_problems.push(problem),
+                        }
+                    }
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+    // The report shows the values that are left after the relations dropped
+    // conflicting fallbacks, so it comes after them.
+    let (layers, print_config) = if let Some(default_path) = &settings.config {
+        let report = arguments.iter().map(|argument| {
+            let storage_name = format_ident!("__fire_value_{}", argument.ident);
+            let cli_name = &argument.cli_name;
+            let has_value = has_value(argument);
+            let shown = match argument.kind {
+                _ if argument.settings.secret => quote! {
+                    #has_value.then(|| #REDACTED.to_string())
+                },
+                ArgumentKind::Required | ArgumentKind::Optional => quote! {
+                    #storage_name.as_ref().map(|value| value.to_string_lossy().into_owned())
+                },
+                ArgumentKind::OptionalValue => quote! {
+                    #storage_name.as_ref().map(|value| match value {
+                        Some(value) => value.to_string_lossy().into_owned(),
+                        None => "(no value)".to_string(),
+                    })
+                },
+                ArgumentKind::Flag if is_bool(&argument.ty) => {
+                    let default = argument.settings.default;
+                    quote! { Some(#storage_name.unwrap_or(#default).to_string()) }
+                }
+                ArgumentKind::Flag => quote! { #storage_name.map(|value| value.to_string()) },
+                ArgumentKind::Count => quote! { Some(#storage_name.to_string()) },
+                ArgumentKind::Repeated => quote! {
+                    (!#storage_name.is_empty()).then(|| {
+                        #storage_name
+                            .iter()
+                            .map(|value| value.to_string_lossy())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    })
+                },
+            };
+            quote! {
+                match #shown {
+                    Some(value) => {
+                        let recorded = __fire_sources.iter().find(|(name, _)| *name == #cli_name);
+                        let source = match recorded {
+                            Some((_, source)) => source.as_str(),
+                            None if #has_value => "command line",
+                            None => "default",
+                        };
+                        report.push_str(&format!("--{} = {}  # {}\n", #cli_name, value, source));
+                    }
+                    None => report.push_str(&format!("--{} is not set\n", #cli_name)),
+                }
+            }
+        });
+        let names = arguments.iter().map(|argument| &argument.cli_name);
+        // Commands of a module find the names of all commands next to them.
+        let commands = if command_name.is_empty() {
+            quote! { &[] }
+        } else {
+            quote! { __FIRE_COMMANDS }
+        };
+        let layers = quote! {
+            let __fire_config = ::fire::__private::Config::load(
+                __fire_config_path.as_deref(),
+                #default_path,
+            )
+            .map_err(__fire_error)?;
+            if let Some(config) = &__fire_config {
+                __fire_problems.extend(config.unknown_tables(#commands));
+                __fire_problems.extend(config.unknown_keys(#command_name, &[#(#names),*]));
+            }
+            let mut __fire_sources: Vec<(&str, String)> = Vec::new();
+            #(#fallbacks)*
+        };
+        let print_config = quote! {
+            if __fire_print_config && __fire_problems.is_empty() {
+                let mut report = String::new();
+                #(#report)*
+                return Ok(Some(report.trim_end().to_string()));
+            }
+        };
+        (layers, print_config)
+    } else if fallbacks.is_empty() {
+        (quote! {}, quote! {})
+    } else {
+        let layers = quote! {
+            let __fire_config: Option<::fire::__private::Config> = None;
+            #(#fallbacks)*
+        };
+        (layers, quote! {})
+    };
+
+    // On a terminal, missing required options are asked for before anything
+    // is converted, as long as nothing else is wrong with the command line. An
+    // answer that does not parse is reported and asked for again.
+    let prompts = arguments
+        .iter()
+        .filter(|argument| {
+            matches!(argument.kind, ArgumentKind::Required)
+                && (settings.prompt || argument.settings.prompt)
+        })
+        .map(|argument| {
+            let storage_name = format_ident!("__fire_value_{}", argument.ident);
+            let cli_name = &argument.cli_name;
+            let ty = &argument.ty;
+            let question = summary(&markdown::render(&argument.description));
+            let question = if question.is_empty() {
+                format!("--{cli_name}")
+            } else {
+                question
+            };
+            let parsed = parsed_value(quote! { (&value) }, ty, argument);
+            let secret = argument.settings.secret;
+            quote! {
+                if #storage_name.is_none()
+                    && !__fire_without_value.contains(&#cli_name)
+                    && __fire_problems.is_empty()
+                    && ::fire::__private::interactive()
+                {
+                    let choices = {
+                        use ::fire::__private::{ViaChoices as _, WithoutChoices as _};
+                        (&::fire::__private::Offer::<#ty>(std::marker::PhantomData)).choices()
+                    };
+                    let answer = ::fire::__private::prompt(#question, choices, #secret, |answer| {
+                        let value = std::ffi::OsString::from(answer);
+                        #parsed.map(|_| ())
+                    });
+                    #storage_name = answer.map(std::ffi::OsString::from);
+                }
+            }
+        });
+
+    // Each conversion binds `Some(value)`, or records a problem and binds
+    // `None`, so that every problem is reported together.
+    let conversions = arguments.iter().map(|argument| {
+        let ident = &argument.ident;
+        let ty = &argument.ty;
+        let storage_name = format_ident!("__fire_value_{}", ident);
+        let cli_name = &argument.cli_name;
+        let converted = match argument.kind {
+            ArgumentKind::Flag if is_bool(ty) => {
+                let default = argument.settings.default;
+                quote! { Some(#storage_name.unwrap_or(#default)) }
+            }
+            ArgumentKind::Flag => quote! { Some(#storage_name) },
+            ArgumentKind::Count if is_count(ty) => quote! { Some(::fire::Count(#storage_name)) },
+            ArgumentKind::Count => {
+                quote! { Some(<#ty as ::fire::__private::Integer>::from_count(#storage_name)) }
+            }
+            ArgumentKind::Optional => {
+                let inner = inner_type(ty, "Option").expect("optional type checked above");
+                let parsed = parsed_value(quote! { value }, inner, argument);
+                quote! {
+                    match #storage_name.as_ref() {
+                        Some(value) => match #parsed {
+                            Ok(value) => Some(Some(value)),
+                            Err(problem) => {
+                                __fire_problems.push(problem);
+                                None
+                            }
+                        },
+                        None => Some(None),
+                    }
+                }
+            }
+            ArgumentKind::OptionalValue => {
+                let inner = value_type(argument);
+                let parsed = parsed_value(quote! { value }, inner, argument);
+                quote! {
+                    match #storage_name.as_ref() {
+                        Some(Some(value)) => match #parsed {
+                            Ok(value) => Some(Some(Some(value))),
+                            Err(problem) => {
+                                __fire_problems.push(problem);
+                                None
+                            }
+                        },
+                        Some(None) => Some(Some(None)),
+                        None => Some(None),
+                    }
+                }
+            }
+            ArgumentKind::Required => {
+                let parsed = parsed_value(quote! { value }, ty, argument);
+                quote! {
+                    match #storage_name.as_ref() {
+                        Some(value) => match #parsed {
+                            Ok(value) => Some(value),
+                            Err(problem) => {
+                                __fire_problems.push(problem);
+                                None
+                            }
+                        },
+                        None => {
+                            if !__fire_without_value.contains(&#cli_name) {
+                                __fire_problems
+                                    .push(format!("missing required option '--{}'", #cli_name));
+                            }
+                            None
+                        }
+                    }
+                }
+            }
+            ArgumentKind::Repeated => {
+                // A key given twice in a map is reported rather than letting
+                // the last entry win.
+                let (element, collection, add) = match map_types(ty) {
+                    Some((key, value)) => {
+                        let key_text = if argument.settings.secret {
+                            quote! { #REDACTED }
+                        } else {
+                            quote! { value.to_string_lossy().split('=').next().unwrap_or_default() }
+                        };
+                        let add = quote! {
+                            let (key, entry) = parsed;
+                            if values.insert(key, entry).is_some() {
+                                __fire_problems.push(format!(
+                                    "option '--{}' was given key '{}' more than once",
+                                    #cli_name,
+                                    #key_text
+                                ));
+                                valid = false;
+                            }
+                        };
+                        (syn::parse_quote!((#key, #value)), quote! { #ty }, add)
+                    }
+                    None => {
+                        let element = value_type(argument).clone();
+                        let collection = quote! { Vec<#element> };
+                        (element, collection, quote! { values.push(parsed); })
+                    }
+                };
+                let parsed = parsed_value(quote! { value }, &element, argument);
+                return quote! {
+                    let #ident: Option<#collection> = {
+                        let mut values: #collection = Default::default();
+                        let mut valid = true;
+                        for value in &#storage_name {
+                            match #parsed {
+                                Ok(parsed) => {
+                                    #add
+                                }
+                                Err(problem) => {
+                                    __fire_problems.push(problem);
+                                    valid = false;
+                                }
+                            }
+                        }
+                        valid.then_some(values)
+                    };
+                };
+            }
+        };
+        quote! { let #ident: Option<#ty> = #converted; }
+    });
+    let unwrapped = arguments.iter().map(|argument| {
+        let ident = &argument.ident;
+        quote! { let #ident = #ident.expect("argument problems are reported above"); }
+    });
+
+    let problems = quote! {
+        if __fire_problems.len() == 1 {
+            __fire_problems.remove(0)
+        } else {
+            let mut message = format!("found {} problems:", __fire_problems.len());
+            for problem in &__fire_problems {
+                for (index, line) in problem.lines().filter(|line| !line.is_empty()).enumerate() {
+                    message.push_str(if index == 0 { "\n  - " } else { "\n    " });
+                    message.push_str(line);
+                }
+            }
+            message
+        }
+    };
+    let call_arguments = arguments.iter().map(|argument| {
+        let ident = &argument.ident;
+        if slice_element(&argument.ty).is_some() {
+            quote! { &#ident }
+        } else {
+            quote! { #ident }
+        }
+    });
+    let mut invocation = quote! { #function_name(#(#call_arguments),*) };
+    if function.sig.asyncness.is_some() {
+        invocation = quote! {
+            ::tokio::runtime::Builder::new_multi_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build tokio runtime")
+                .block_on(#invocation)
+        };
+    }
+    let call = match &function.sig.output {
+        ReturnType::Type(_, ty) if inner_type(ty, "Result").is_some() => quote! {
+            #invocation
+                .map(|_| None)
+                .map_err(|error| error.to_string())
+        },
+        _ => quote! {
+            #invocation;
+            Ok(None)
+        },
+    };
+
+    Ok(quote! {
+        #[doc(hidden)]
+        #visibility fn #runner_name<I, S>(input: I) -> Result<Option<String>, String>
+        where
+            I: IntoIterator<Item = S>,
+            S: Into<std::ffi::OsString>,
+        {
+            let program = #program_name;
+            let __fire_usage = #usage.replace("{program}", &program);
+            let __fire_error = |message: String| {
+                format!(
+                    "{}\n\n{}\n\nFor more information, try '--help'.",
+                    message, __fire_usage
+                )
+            };
+            #arguments_binding = #collected_arguments;
+            #builtin_setup
+            let __fire_styled = #styled;
+            match __fire_args
+                .iter()
+                .find(|argument| *argument == "--help" || *argument == "-h")
+                .and_then(|argument| argument.to_str())
+            {
+                Some("-h") => {
+                    let help = if __fire_styled { #styled_help } else { #help };
+                    return Ok(Some(help.replace("{program}", &program)));
+                }
+                Some(_) => {
+                    let help = if __fire_styled { #styled_long_help } else { #long_help };
+                    return Ok(Some(help.replace("{program}", &program)));
+                }
+                None => {}
+            }
+            #(#storage)*
+            #yes_storage
+
+            let mut __fire_problems: Vec<String> = Vec::new();
+            let mut __fire_without_value: Vec<&'static str> = Vec::new();
+            let mut __fire_index = 0usize;
+            while __fire_index < __fire_args.len() {
+                'argument: {
+                    let __fire_raw = &__fire_args[__fire_index];
+                    let (__fire_key, __fire_inline_value) =
+                        ::fire::__private::split_option(__fire_raw);
+                    let __fire_key = __fire_key.as_str();
+                    #resolve_option
+                    let mut __fire_matched = false;
+                    #(#option_matches)*
+                    #yes_match
+                    #short_matches
+                    if !__fire_matched {
+                        let mut problem =
+                            format!("unexpected argument '{}'", __fire_raw.to_string_lossy());
+                        if __fire_key.starts_with("--") {
+                            if let Some(similar) = #option_suggestion {
+                                problem.push_str(&format!(
+                                    "\n\n  tip: a similar argument exists: '{}'",
+                                    similar
+                                ));
+                            }
+                        }
+                        __fire_problems.push(problem);
+                        // The unknown option most likely takes the following
+                        // value, which should not be reported on its own.
+                        let next_is_value = __fire_args
+                            .get(__fire_index + 1)
+                            .is_some_and(|next| !next.as_encoded_bytes().starts_with(b"-"));
+                        if __fire_inline_value.is_none() && __fire_key.starts_with("--") && next_is_value {
+                            __fire_index += 1;
+                        }
+                    }
+                }
+                __fire_index += 1;
+            }
+
+            #layers
+            #(#relations)*
+            #print_config
+            #(#prompts)*
+            #confirmation
+            #(#conversions)*
+            if !__fire_problems.is_empty() {
+                return Err(__fire_error(#problems));
+            }
+            #(#unwrapped)*
+            #call
+        }
+    })
+}
+
+/// Generates `fn main()`. With `color_option`, the `error:` prefix follows a
+/// `--color` option on the command line like the rest of the output does.
+fn entrypoint(call: TokenStream2, color_option: bool) -> TokenStream2 {
+    let choice = if color_option {
+        quote! {
+            std::env::args_os()
+                .map(|argument| argument.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+                .windows(2)
+                .rev()
+                .find_map(|pair| match pair[1].strip_prefix("--color=") {
+                    Some(value) => Some(value.to_string()),
+                    None if pair[0] == "--color" => Some(pair[1].clone()),
+                    None => None,
+                })
+        }
+    } else {
+        quote! { None::<String> }
+    };
+    let styled = use_color(
+        quote! { __fire_color.as_deref() },
+        quote! { std::io::stderr() },
+    );
+    let error_prefix = format!("{}error:{}", style::ERROR, style::RESET);
+    quote! {
+        fn main() {
+            ::fire::__private::print_results();
+            match #call {
+                Ok(Some(help)) => println!("{}", help),
+                Ok(None) => {}
+                Err(error) => {
+                    let __fire_color = #choice;
+                    let prefix = if #styled { #error_prefix } else { "error:" };
+                    eprintln!("{} {}", prefix, error);
+                    std::process::exit(2);
+                }
+            }
+        }
+    }
+}
+
+fn expand_function(mut function: ItemFn, settings: &Settings) -> syn::Result<TokenStream2> {
+    if function.sig.ident == "main" {
+        return Err(syn::Error::new_spanned(
+            &function.sig.ident,
+            "put #[fire::main] on the command function, not on a function named `main`",
+        ));
+    }
+    if settings.repl {
+        return Err(syn::Error::new_spanned(
+            &function.sig.ident,
+            "`repl` adds a `shell` command, so it requires a command module",
+        ));
+    }
+    let runner_name = format_ident!("__fire_run_{}", function.sig.ident);
+    let color_option = !declares_color(&function);
+    let runner = command_runner(&mut function, &runner_name, quote! { pub(crate) }, "", settings)?;
+    let main = entrypoint(
+        quote! { #runner_name(std::env::args_os().skip(1)) },
+        color_option,
+    );
+    Ok(quote! { #function #runner #main })
+}
+
+struct Command {
+    name: String,
+    runner: Ident,
+    description: String,
+    color: bool,
+}
+
+/// Builds the help text for a command module. Commands are always listed by
+/// their summary; long help additionally shows the full module description.
+fn module_help(description: &str, commands: &[Command], settings: &Settings, long: bool) -> String {
+    let mut help = String::new();
+    let (description, examples) = markdown::examples(description);
+    push_description(&mut help, &description, long);
+    help.push_str("Usage: {program} <COMMAND>\n\nCommands:\n");
+    for command in commands {
+        help.push_str(&format!("    {}", command.name));
+        let summary = summary(&markdown::render(&command.description));
+        if !summary.is_empty() {
+            help.push_str("    ");
+            help.push_str(&summary);
+        }
+        help.push('\n');
+    }
+    if settings.repl {
+        help.push_str(&format!("    {SHELL}    Start an interactive shell\n"));
+    }
+    help.push_str("\nOptions:\n");
+    help.push_str(COLOR_HELP);
+    if settings.config.is_some() {
+        help.push_str(CONFIG_HELP);
+    }
+    help.push_str(BATCH_HELP);
+    help.push_str("    -h, --help    Print help");
+    if long {
+        push_examples(&mut help, &examples);
+    }
+    align_columns(&help)
+}
+
+/// Pads the names of every `Options:` and `Commands:` entry so that their
+/// descriptions start in the same column within each list.
+fn align_columns(help: &str) -> String {
+    // Pairs each line with the list it belongs to, if any.
+    let mut list = None;
+    let mut count = 0;
+    let lines: Vec<(&str, Option<usize>)> = help
+        .split('\n')
+        .map(|line| {
+            if matches!(line, "Options:" | "Commands:") {
+                list = Some(count);
+                count += 1;
+            } else if line == "Examples:" {
+                list = None;
+            }
+            (line, list)
+        })
+        .collect();
+    let mut widths = vec![0; count];
+    for (line, list) in &lines {
+        if let (Some((name, _)), Some(list)) = (style::entry(line), list) {
+            widths[*list] = widths[*list].max(name.len());
+        }
+    }
+    lines
+        .iter()
+        .map(|(line, list)| match (style::entry(line), list) {
+            (Some((name, description)), Some(list)) if !description.is_empty() => {
+                format!("    {name:width$}{description}", width = widths[*list])
+            }
+            _ => line.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn expand_module(mut module: ItemMod, settings: &Settings) -> syn::Result<TokenStream2> {
+    let module_name = module.ident.clone();
+    let module_description = documentation(&module.attrs);
+    let Some((_, items)) = &mut module.content else {
+        return Err(syn::Error::new_spanned(
+            &module,
+            "#[fire::main] requires an inline module",
+        ));
+    };
+
+    let mut commands = Vec::new();
+    let mut runners = Vec::new();
+    for item in items.iter_mut() {
+        let Item::Fn(function) = item else {
+            continue;
+        };
+        let command_name = kebab_case(&function.sig.ident.to_string());
+        if settings.repl && command_name == SHELL {
+            return Err(syn::Error::new_spanned(
+                &function.sig.ident,
+                "`shell` is the built-in command of #[fire::main(repl)]",
+            ));
+        }
+        if settings.repl && SHELL_WORDS.contains(&command_name.as_str()) {
+            return Err(syn::Error::new_spanned(
+                &function.sig.ident,
+                format!("`{command_name}` is a built-in word of the #[fire::main(repl)] shell"),
+            ));
+        }
+        let runner_name = format_ident!("__fire_run_{}", function.sig.ident);
+        let description = documentation(&function.attrs);
+        let color = !declares_color(function);
+        runners.push(command_runner(
+            function,
+            &runner_name,
+            quote! {},
+            &command_name,
+            settings,
+        )?);
+        commands.push(Command {
+            name: command_name,
+            runner: runner_name,
+            description,
+            color,
+        });
+    }
+
+    for runner in runners {
+        items.push(syn::parse2(runner).expect("generated command runner"));
+    }
+    if settings.config.is_some() {
+        let names = commands.iter().map(|command| &command.name);
+        items.push(syn::parse_quote! {
+            #[doc(hidden)]
+            const __FIRE_COMMANDS: &[&str] = &[#(#names),*];
+        });
+    }
+    // Built-in options given before the command are passed on to it.
+    let forward_config = if settings.config.is_some() {
+        quote! {
+            if let Some(path) = &__fire_config_path {
+                let mut option = std::ffi::OsString::from("--config=");
+                option.push(path);
+                arguments.insert(0, option);
+            }
+            if __fire_print_config {
+                arguments.insert(0, "--fire-print-config".into());
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let dispatch = commands.iter().map(|command| {
+        let Command { name, runner, .. } = command;
+        if command.color {
+            quote! {
+                #name => {
+                    if let Some(color) = &__fire_color {
+                        arguments.insert(0, format!("--color={}", color).into());
+                    }
+                    #runner(arguments)
+                }
+            }
+        } else {
+            quote! { #name => #runner(arguments), }
+        }
+    });
+    let root_help = module_help(&module_description, &commands, settings, false);
+    let root_long_help = module_help(&module_description, &commands, settings, true);
+    let root_usage = root_help
+        .lines()
+        .find(|line| line.starts_with("Usage:"))
+        .expect("root help always contains usage")
+        .to_string();
+    let mut command_names: Vec<String> = commands
+        .iter()
+        .map(|command| command.name.clone())
+        .collect();
+    // The shell runs each line through this dispatcher again.
+    let shell = if settings.repl {
+        command_names.push(SHELL.to_string());
+        quote! {
+            #SHELL => {
+                if let Some(argument) = arguments.first() {
+                    return Err(__fire_error(format!(
+                        "unexpected argument '{}'",
+                        argument.to_string_lossy()
+                    )));
+                }
+                ::fire::__private::shell(&program, |arguments: Vec<std::ffi::OsString>| {
+                    __fire_run(arguments)
+                })
+            }
+        }
+    } else {
+        quote! {}
+    };
+    let command_suggestion = suggestion(quote! { &command }, &command_names);
+    let resolve_command = if settings.abbrev {
+        let resolved = abbreviation(quote! { &command }, &command_names, "command");
+        quote! { let command = #resolved.map_err(__fire_error)?.to_string(); }
+    } else {
+        quote! {}
+    };
+    let styled_root_help = style::help(&root_help);
+    let styled_root_long_help = style::help(&root_long_help);
+    let program_name = program_name();
+    let builtin_setup = builtin_options(true, true, settings.config.is_some());
+    let styled = use_color(
+        quote! { __fire_color.as_deref() },
+        quote! { std::io::stdout() },
+    );
+    items.push(
+        syn::parse2(quote! {
+            #[doc(hidden)]
+            pub(crate) fn __fire_run<I, S>(input: I) -> Result<Option<String>, String>
+            where
+                I: IntoIterator<Item = S>,
+                S: Into<std::ffi::OsString>,
+            {
+                let mut __fire_args: Vec<std::ffi::OsString> =
+                    input.into_iter().map(Into::into).collect();
+                let program = #program_name;
+                let __fire_usage = #root_usage.replace("{program}", &program);
+                let __fire_error = |message: String| {
+                    format!(
+                        "{}\n\n{}\n\nFor more information, try '--help'.",
+                        message, __fire_usage
+                    )
+                };
+                #builtin_setup
+                let __fire_styled = #styled;
+                // Each command expands response files among its own
+                // arguments, so only one in place of the command is read here.
+                ::fire::__private::expand_leading_response_file(&mut __fire_args)
+                    .map_err(__fire_error)?;
+                if __fire_args.is_empty() {
+                    return Err(__fire_error("missing command".to_string()));
+                }
+                // `--fire-batch <FILE|->` runs one command line per line of
+                // the file through this dispatcher.
+                let (__fire_key, __fire_inline_value) =
+                    ::fire::__private::split_option(&__fire_args[0]);
+                if __fire_key == "--fire-batch" {
+                    __fire_args.remove(0);
+                    let path = match __fire_inline_value {
+                        Some(path) => path,
+                        None if !__fire_args.is_empty() => __fire_args.remove(0),
+                        None => {
+                            return Err(__fire_error(
+                                "option '--fire-batch' requires a value".to_string(),
+                            ));
+                        }
+                    };
+                    if let Some(argument) = __fire_args.first() {
+                        return Err(__fire_error(format!(
+                            "unexpected argument '{}'",
+                            argument.to_string_lossy()
+                        )));
+                    }
+                    return ::fire::__private::batch(&path, |arguments: Vec<std::ffi::OsString>| {
+                        __fire_run(arguments)
+                    });
+                }
+                let command = __fire_args.remove(0).to_string_lossy().into_owned();
+                if command == "-h" {
+                    let help = if __fire_styled { #styled_root_help } else { #root_help };
+                    return Ok(Some(help.replace("{program}", &program)));
+                }
+                if command == "--help" {
+                    let help = if __fire_styled {
+                        #styled_root_long_help
+                    } else {
+                        #root_long_help
+                    };
+                    return Ok(Some(help.replace("{program}", &program)));
+                }
+                #[allow(unused_mut)]
+                let mut arguments = __fire_args;
+                #forward_config
+                #resolve_command
+                match command.as_str() {
+                    #(#dispatch)*
+                    #shell
+                    _ => {
+                        let mut message = format!("unknown command '{}'", command);
+                        if let Some(similar) = #command_suggestion {
+                            message.push_str(&format!(
+                                "\n\n  tip: a similar command exists: '{}'",
+                                similar
+                            ));
+                        }
+                        Err(__fire_error(message))
+                    }
+                }
+            }
+        })
+        .expect("generated command dispatcher"),
+    );
+
+    let main = entrypoint(
+        quote! { #module_name::__fire_run(std::env::args_os().skip(1)) },
+        true,
+    );
+    Ok(quote! { #module #main })
+}
+
+/// Implementation of `#[fire::main]`. Use it through the `fire` crate, which
+/// documents it.
+#[proc_macro_attribute]
+pub fn main(metadata: TokenStream, input: TokenStream) -> TokenStream {
+    let settings = match Settings::parse(metadata.into()) {
+        Ok(settings) => settings,
+        Err(error) => return error.into_compile_error().into(),
+    };
+    let item = parse_macro_input!(input as Item);
+    let expanded = match item {
+        Item::Fn(function) => expand_function(function, &settings),
+        Item::Mod(module) => expand_module(module, &settings),
+        other => Err(syn::Error::new_spanned(
+            other,
+            "#[fire::main] only supports functions and inline modules",
+        )),
+    };
+    expanded
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,162 @@
+//! Rendering of documentation comments for the terminal.
+//!
+//! Documentation comments are written in Markdown for rustdoc. Help output
+//! only needs a readable plain-text version, so this module handles the
+//! constructs that commonly appear in command documentation and leaves
+//! everything else untouched.
+
+/// Renders Markdown as plain text: headings become `Heading:` lines, list items
+/// get bullets, fenced code is indented, and inline markup is removed.
+pub(crate) fn render(text: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+            continue;
+        }
+        if in_code {
+            lines.push(format!("    {line}").trim_end().to_string());
+        } else if let Some((_, heading)) = heading(trimmed) {
+            lines.push(format!("{}:", inline(heading)));
+        } else if let Some(item) = list_item(trimmed) {
+            lines.push(format!("{indent}• {}", inline(item)));
+        } else {
+            lines.push(format!("{indent}{}", inline(trimmed)));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Splits out an `# Examples` section, returning the remaining documentation
+/// and the body of the section.
+pub(crate) fn examples(text: &str) -> (String, String) {
+    let mut rest = Vec::new();
+    let mut examples = Vec::new();
+    let mut level = None;
+    let mut in_code = false;
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") {
+            in_code = !in_code;
+        } else if !in_code {
+            if let Some((heading_level, title)) = heading(trimmed) {
+                match level {
+                    Some(section) if heading_level > section => {}
+                    _ if matches!(title, "Examples" | "Example") => {
+                        level = Some(heading_level);
+                        continue;
+                    }
+                    _ => level = None,
+                }
+            }
+        }
+        if level.is_some() {
+            examples.push(line);
+        } else {
+            rest.push(line);
+        }
+    }
+    (
+        rest.join("\n").trim_matches('\n').to_string(),
+        examples.join("\n").trim_matches('\n').to_string(),
+    )
+}
+
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line
+        .chars()
+        .take_while(|character| *character == '#')
+        .count();
+    if level == 0 {
+        return None;
+    }
+    let title = line[level..].strip_prefix(' ')?;
+    Some((level, title.trim()))
+}
+
+fn list_item(line: &str) -> Option<&str> {
+    ["- ", "* ", "+ "]
+        .iter()
+        .find_map(|marker| line.strip_prefix(marker))
+}
+
+/// Removes inline markup: code ticks, emphasis markers and link syntax. URL
+/// link targets are kept after the link text; intra-doc targets are dropped.
+fn inline(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(character) = rest.chars().next() {
+        let previous = text[..text.len() - rest.len()].chars().next_back();
+        if character == '`' {
+            if let Some(end) = rest[1..].find('`') {
+                output.push_str(&rest[1..=end]);
+                rest = &rest[end + 2..];
+                continue;
+            }
+        } else if character == '[' {
+            if let Some((label, target, remaining)) = link(rest) {
+                output.push_str(&inline(label));
+                if let Some(target) = target.filter(|target| target.contains("://")) {
+                    output.push_str(&format!(" ({target})"));
+                }
+                rest = remaining;
+                continue;
+            }
+        } else if let Some((marker, end)) = emphasis(previous, rest) {
+            output.push_str(&inline(&rest[marker..end]));
+            rest = &rest[end + marker..];
+            continue;
+        }
+        output.push(character);
+        rest = &rest[character.len_utf8()..];
+    }
+    output
+}
+
+/// Parses `[label](target)`, a reference `[label][target]`, or an intra-doc
+/// link to code such as ``[`Config`]`` at the start of `text`. Other brackets,
+/// such as `[0-9]` or `[default: 1]`, are not links.
+fn link(text: &str) -> Option<(&str, Option<&str>, &str)> {
+    let end = text.find(']')?;
+    let label = &text[1..end];
+    let after = &text[end + 1..];
+    if let Some(target) = after.strip_prefix('(') {
+        let close = target.find(')')?;
+        return Some((label, Some(&target[..close]), &target[close + 1..]));
+    }
+    if let Some(target) = after.strip_prefix('[') {
+        let close = target.find(']')?;
+        return Some((label, Some(&target[..close]), &target[close + 1..]));
+    }
+    let code = label.len() > 2
+        && label.starts_with('`')
+        && label.ends_with('`')
+        && !label[1..label.len() - 1].contains('`');
+    code.then_some((label, None, after))
+}
+
+/// Finds emphasis with `**`, `__`, or `*` that starts at the beginning of
+/// `text`, returning the length of the marker and where the emphasized text
+/// ends. Markers only count at word boundaries and next to the text they
+/// enclose, so `a * b`, `2*3*4`, and `snake_case` are left alone, and so is
+/// `__init__`, since a single identifier between `__` is a name rather than
+/// bold text.
+fn emphasis(previous: Option<char>, text: &str) -> Option<(usize, usize)> {
+    let marker = ["**", "__", "*"]
+        .into_iter()
+        .find(|marker| text.starts_with(marker))?;
+    let word = |character: char| character.is_alphanumeric() || character == '_';
+    if previous.is_some_and(word) {
+        return None;
+    }
+    let inner = &text[marker.len()..];
+    let end = inner.find(marker)?;
+    let body = &inner[..end];
+    let closed = !inner[end + marker.len()..].starts_with(word);
+    let tight = !body.is_empty() && body.trim() == body;
+    let name = marker == "__" && body.chars().all(word);
+    (closed && tight && !name).then_some((marker.len(), marker.len() + end))
+}
@@ -0,0 +1,63 @@
+//! Terminal styling of generated help.
+//!
+//! Help text is known at compile time, so a styled copy is prepared next to
+//! the plain one and the generated code only chooses between them.
+
+pub(crate) const HEADING: &str = "\x1b[1;4m";
+pub(crate) const LITERAL: &str = "\x1b[1;36m";
+pub(crate) const ERROR: &str = "\x1b[1;31m";
+pub(crate) const RESET: &str = "\x1b[0m";
+
+/// Returns a copy of plain help text with bold section headings and colored
+/// option and command names.
+pub(crate) fn help(plain: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_list = false;
+    for line in plain.split('\n') {
+        if let Some(usage) = line.strip_prefix("Usage:") {
+            lines.push(format!("{HEADING}Usage:{RESET}{usage}"));
+        } else if matches!(line, "Options:" | "Commands:") {
+            in_list = true;
+            lines.push(format!("{HEADING}{line}{RESET}"));
+        } else if line == "Examples:" {
+            in_list = false;
+            lines.push(format!("{HEADING}{line}{RESET}"));
+        } else if let Some((name, description)) = entry(line).filter(|_| in_list) {
+            lines.push(format!("    {}{description}", names(name)));
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+/// Splits an `Options:` or `Commands:` entry into its name column and the
+/// rest of the line.
+pub(crate) fn entry(line: &str) -> Option<(&str, &str)> {
+    let line = line.strip_prefix("    ")?;
+    if line.starts_with(' ') || line.is_empty() {
+        return None;
+    }
+    Some(match line.find("    ") {
+        Some(end) => line.split_at(end),
+        None => (line, ""),
+    })
+}
+
+fn names(column: &str) -> String {
+    column
+        .split(' ')
+        .map(|word| {
+            if word.starts_with('<') {
+                word.to_string()
+            } else {
+                let (word, separator) = match word.strip_suffix(',') {
+                    Some(word) => (word, ","),
+                    None => (word, ""),
+                };
+                format!("{LITERAL}{word}{RESET}{separator}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
@@ -0,0 +1,614 @@
+//! Option defaults read from a configuration file, for code generated by
+//! `#[fire::main(config = "...")]`. Not public API.
+//!
+//! Files ending in `.json` hold a JSON object; anything else is read as TOML.
+//! Only what option values need is supported: strings, numbers, booleans, and
+//! arrays of those, at the top level or in one table per command. Everything
+//! else is rejected rather than misread: in TOML, multi-line strings, dates,
+//! dotted keys, nested tables, inline tables, and arrays of tables; in JSON,
+//! nested objects below a command. Keys may only be defined once.
+
+use std::ffi::{OsStr, OsString};
+use std::iter::Peekable;
+use std::path::{Path, PathBuf};
+use std::str::Chars;
+
+use crate::private::suggestion;
+
+/// A value given for an option by a configuration file or an environment
+/// variable.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    Text(OsString),
+    List(Vec<OsString>),
+}
+
+/// A value that did not come from the command line, with where it came from.
+pub struct Fallback {
+    pub value: ConfigValue,
+    pub source: String,
+}
+
+/// The contents of a configuration file: top-level values under the empty
+/// name, followed by one table per command.
+#[derive(Debug)]
+pub struct Config {
+    path: PathBuf,
+    tables: Vec<(String, Vec<(String, ConfigValue)>)>,
+}
+
+impl Config {
+    /// Reads the file given with `--config`, or else `default` if it exists.
+    pub fn load(explicit: Option<&OsStr>, default: &str) -> Result<Option<Config>, String> {
+        let path = match explicit {
+            Some(path) => PathBuf::from(path),
+            None if Path::new(default).exists() => PathBuf::from(default),
+            None => return Ok(None),
+        };
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|error| format!("cannot read config file '{}': {}", path.display(), error))?;
+        let is_json = path
+            .extension()
+            .is_some_and(|extension| extension == "json");
+        let tables = if is_json {
+            parse_json(&contents)
+        } else {
+            parse_toml(&contents)
+        }
+        .map_err(|problem| format!("invalid config file '{}': {}", path.display(), problem))?;
+        Ok(Some(Config { path, tables }))
+    }
+
+    /// Finds the value of the option named `key`, in the table of `command`
+    /// before the top level. Keys may use `-` or `_` between words.
+    pub fn lookup(&self, command: &str, key: &str) -> Option<&ConfigValue> {
+        let find = |name: &str| {
+            self.tables
+                .iter()
+                .filter(|(table, _)| table == name)
+                .flat_map(|(_, values)| values)
+                .find(|(candidate, _)| candidate.replace('_', "-") == key)
+                .map(|(_, value)| value)
+        };
+        if command.is_empty() {
+            find("")
+        } else {
+            find(command).or_else(|| find(""))
+        }
+    }
+
+    /// Lists keys of the table that belongs to `command` which name none of
+    /// `options`. The top level of a command module is shared by all commands,
+    /// so it is only checked for a single command.
+    pub fn unknown_keys(&self, command: &str, options: &[&'static str]) -> Vec<String> {
+        self.tables
+            .iter()
+            .filter(|(table, _)| table == command)
+            .flat_map(|(_, values)| values)
+            .filter(|(key, _)| !options.contains(&key.replace('_', "-").as_str()))
+            .map(|(key, _)| self.unknown("option", key, options))
+            .collect()
+    }
+
+    /// Lists tables that name none of `commands`, which is empty for an
+    /// application without commands.
+    pub fn unknown_tables(&self, commands: &[&'static str]) -> Vec<String> {
+        self.tables
+            .iter()
+            .map(|(table, _)| table)
+            .filter(|table| !table.is_empty() && !commands.contains(&table.as_str()))
+            .map(|table| self.unknown("command", table, commands))
+            .collect()
+    }
+
+    fn unknown(&self, kind: &str, name: &str, candidates: &[&'static str]) -> String {
+        let mut problem = format!(
+            "unknown {} '{}' in config file '{}'",
+            kind,
+            name,
+            self.path.display()
+        );
+        if let Some(similar) = suggestion(&name.replace('_', "-"), candidates) {
+            problem.push_str(&format!("\n\n  tip: a similar {kind} exists: '{similar}'"));
+        }
+        problem
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Finds the value for an option that was not given on the command line: the
+/// environment variable `env` first, then the configuration file.
+pub fn fallback(
+    env: Option<&str>,
+    config: Option<&Config>,
+    command: &str,
+    key: &str,
+) -> Option<Fallback> {
+    if let Some((name, value)) = env.and_then(|name| Some((name, std::env::var_os(name)?))) {
+        return Some(Fallback {
+            value: ConfigValue::Text(value),
+            source: format!("environment variable {name}"),
+        });
+    }
+    let config = config?;
+    Some(Fallback {
+        value: config.lookup(command, key)?.clone(),
+        source: format!("config file '{}'", config.path.display()),
+    })
+}
+
+impl Fallback {
+    fn invalid(&self, key: &str, expected: &str) -> String {
+        format!(
+            "invalid value for '--{}' from {} (expected {})",
+            key, self.source, expected
+        )
+    }
+
+    /// The value of an option taking one value.
+    pub fn single(&self, key: &str) -> Result<OsString, String> {
+        match &self.value {
+            ConfigValue::Bool(value) => Ok(value.to_string().into()),
+            ConfigValue::Text(value) => Ok(value.clone()),
+            ConfigValue::List(_) => Err(self.invalid(key, "a single value")),
+        }
+    }
+
+    /// The values of an option that may be repeated.
+    pub fn list(&self, key: &str) -> Result<Vec<OsString>, String> {
+        match &self.value {
+            ConfigValue::List(values) => Ok(values.clone()),
+            _ => self.single(key).map(|value| vec![value]),
+        }
+    }
+
+    /// The state of a flag.
+    pub fn flag(&self, key: &str) -> Result<bool, String> {
+        match &self.value {
+            ConfigValue::Bool(value) => Ok(*value),
+            ConfigValue::Text(value) if value == "true" || value == "1" => Ok(true),
+            ConfigValue::Text(value) if value == "false" || value == "0" => Ok(false),
+            _ => Err(self.invalid(key, "true or false")),
+        }
+    }
+
+    /// How many times a counted flag is given.
+    pub fn count(&self, key: &str) -> Result<u8, String> {
+        match &self.value {
+            ConfigValue::Bool(value) => Ok(u8::from(*value)),
+            ConfigValue::Text(value) => value
+                .to_str()
+                .and_then(|value| value.parse().ok())
+                .ok_or_else(|| self.invalid(key, "a number from 0 to 255")),
+            ConfigValue::List(_) => Err(self.invalid(key, "a number from 0 to 255")),
+        }
+    }
+
+    /// An option with an optional value: `true` or an empty value gives the
+    /// option without a value, and `false` leaves it out.
+    pub fn optional_value(&self, key: &str) -> Result<Option<Option<OsString>>, String> {
+        match &self.value {
+            ConfigValue::Bool(true) => Ok(Some(None)),
+            ConfigValue::Bool(false) => Ok(None),
+            ConfigValue::Text(value) if value.is_empty() => Ok(Some(None)),
+            ConfigValue::Text(value) => Ok(Some(Some(value.clone()))),
+            ConfigValue::List(_) => Err(self.invalid(key, "a single value")),
+        }
+    }
+}
+
+type Tables = Vec<(String, Vec<(String, ConfigValue)>)>;
+
+fn parse_toml(text: &str) -> Result<Tables, String> {
+    let mut tables: Tables = vec![(String::new(), Vec::new())];
+    let mut characters = text.chars().peekable();
+    let mut line = 1;
+    loop {
+        skip_blank(&mut characters, &mut line, true);
+        let Some(&character) = characters.peek() else {
+            break;
+        };
+        let start = line;
+        let at = |problem: &str| format!("{problem} on line {start}");
+        if character == '[' {
+            characters.next();
+            if characters.peek() == Some(&'[') {
+                return Err(at("arrays of tables are not supported"));
+            }
+            let name = toml_key(&mut characters).map_err(|problem| at(&problem))?;
+            skip_spaces(&mut characters);
+            match characters.next() {
+                Some(']') => {}
+                Some('.') => return Err(at("nested tables are not supported")),
+                _ => return Err(at("expected ']' after the table name")),
+            }
+            let defined = tables.iter().any(|(table, _)| *table == name)
+                || tables[0].1.iter().any(|(key, _)| *key == name);
+            if defined {
+                return Err(at(&format!("'{name}' is defined twice")));
+            }
+            tables.push((name, Vec::new()));
+        } else {
+            let key = toml_key(&mut characters).map_err(|problem| at(&problem))?;
+            skip_spaces(&mut characters);
+            match characters.next() {
+                Some('=') => {}
+                Some('.') => return Err(at("dotted keys are not supported")),
+                _ => return Err(at(&format!("expected '=' after '{key}'"))),
+            }
+            skip_spaces(&mut characters);
+            let value = toml_value(&mut characters, &mut line).map_err(|problem| at(&problem))?;
+            let values = &mut tables.last_mut().expect("the top level is a table").1;
+            if values.iter().any(|(existing, _)| *existing == key) {
+                return Err(at(&format!("'{key}' is defined twice")));
+            }
+            values.push((key, value));
+        }
+        skip_spaces(&mut characters);
+        match characters.next() {
+            None => break,
+            Some('\n') => line += 1,
+            Some('#') => skip_comment(&mut characters),
+            Some(_) => return Err(at("expected the end of the line")),
+        }
+    }
+    Ok(tables)
+}
+
+fn skip_spaces(characters: &mut Peekable<Chars<'_>>) {
+    while characters
+        .next_if(|character| matches!(character, ' ' | '\t' | '\r'))
+        .is_some()
+    {}
+}
+
+fn skip_comment(characters: &mut Peekable<Chars<'_>>) {
+    while characters.next_if(|character| *character != '\n').is_some() {}
+}
+
+/// Skips whitespace and, with `comments`, comments, counting lines.
+fn skip_blank(characters: &mut Peekable<Chars<'_>>, line: &mut usize, comments: bool) {
+    loop {
+        match characters.peek() {
+            Some(' ' | '\t' | '\r') => {}
+            Some('\n') => *line += 1,
+            Some('#') if comments => skip_comment(characters),
+            _ => break,
+        }
+        characters.next();
+    }
+}
+
+fn toml_key(characters: &mut Peekable<Chars<'_>>) -> Result<String, String> {
+    skip_spaces(characters);
+    match characters.peek() {
+        Some(&quote @ ('"' | '\'')) => {
+            characters.next();
+            quoted(characters, quote, Syntax::Toml)
+        }
+        _ => {
+            let mut key = String::new();
+            while let Some(character) = characters.next_if(|character| {
+                character.is_ascii_alphanumeric() || matches!(character, '-' | '_')
+            }) {
+                key.push(character);
+            }
+            if key.is_empty() {
+                Err("expected a key".to_string())
+            } else {
+                Ok(key)
+            }
+        }
+    }
+}
+
+fn toml_value(
+    characters: &mut Peekable<Chars<'_>>,
+    line: &mut usize,
+) -> Result<ConfigValue, String> {
+    match characters.peek() {
+        Some('[') => {
+            characters.next();
+            let mut values = Vec::new();
+            loop {
+                skip_blank(characters, line, true);
+                if characters.next_if_eq(&']').is_some() {
+                    break;
+                }
+                match toml_value(characters, line)? {
+                    ConfigValue::Bool(value) => values.push(value.to_string().into()),
+                    ConfigValue::Text(value) => values.push(value),
+                    ConfigValue::List(_) => return Err("nested arrays are not supported".into()),
+                }
+                skip_blank(characters, line, true);
+                if characters.next_if_eq(&',').is_none() && characters.peek() != Some(&']') {
+                    return Err("expected ',' or ']' in array".to_string());
+                }
+            }
+            Ok(ConfigValue::List(values))
+        }
+        Some('{') => Err("inline tables are not supported".to_string()),
+        Some(&quote @ ('"' | '\'')) => {
+            characters.next();
+            if characters.next_if_eq(&quote).is_some() {
+                if characters.peek() == Some(&quote) {
+                    return Err("multi-line strings are not supported".to_string());
+                }
+                return Ok(ConfigValue::Text(OsString::new()));
+            }
+            quoted(characters, quote, Syntax::Toml).map(|text| ConfigValue::Text(text.into()))
+        }
+        _ => {
+            let mut word = String::new();
+            while let Some(character) = characters.next_if(|character| {
+                !character.is_whitespace() && !matches!(character, ',' | ']' | '#')
+            }) {
+                word.push(character);
+            }
+            match word.as_str() {
+                "" => Err("expected a value".to_string()),
+                "true" => Ok(ConfigValue::Bool(true)),
+                "false" => Ok(ConfigValue::Bool(false)),
+                _ if is_toml_number(&word) => Ok(ConfigValue::Text(word.into())),
+                _ => Err(format!("expected a value, not '{word}'; strings must be quoted")),
+            }
+        }
+    }
+}
+
+/// Whether a TOML bare value is a number: an integer, with an optional sign
+/// or a `0x`, `0o`, or `0b` prefix, or a float, including `inf` and `nan`.
+/// Digits may be separated by single underscores.
+fn is_toml_number(word: &str) -> bool {
+    let digits = |part: &str, radix: u32| {
+        !part.is_empty()
+            && !part.starts_with('_')
+            && !part.ends_with('_')
+            && !part.contains("__")
+            && part.chars().all(|digit| digit == '_' || digit.is_digit(radix))
+    };
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(rest) = word.strip_prefix(prefix) {
+            return digits(rest, radix);
+        }
+    }
+    let unsigned = word.strip_prefix(['+', '-']).unwrap_or(word);
+    if matches!(unsigned, "inf" | "nan") {
+        return true;
+    }
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    digits(integer, 10)
+        && (integer == "0" || !integer.starts_with('0'))
+        && fraction.iter().all(|fraction| digits(fraction, 10))
+        && exponent
+            .iter()
+            .all(|exponent| digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent), 10))
+}
+
+/// Whether JSON is being read. Its strings allow `\/` and UTF-16 surrogate
+/// pairs, while TOML strings allow `\U` with eight digits instead.
+#[derive(Clone, Copy, PartialEq)]
+enum Syntax {
+    Json,
+    Toml,
+}
+
+/// Reads the rest of a string opened by `quote`. Double-quoted strings
+/// support the backslash escapes of `syntax`.
+fn quoted(
+    characters: &mut Peekable<Chars<'_>>,
+    quote: char,
+    syntax: Syntax,
+) -> Result<String, String> {
+    let mut text = String::new();
+    loop {
+        match characters.next() {
+            None | Some('\n') => return Err("unterminated string".to_string()),
+            Some(character) if character == quote => return Ok(text),
+            Some('\\') if quote == '"' => text.push(escaped(characters, syntax)?),
+            Some(character) => text.push(character),
+        }
+    }
+}
+
+fn escaped(characters: &mut Peekable<Chars<'_>>, syntax: Syntax) -> Result<char, String> {
+    Ok(match characters.next() {
+        Some('n') => '\n',
+        Some('t') => '\t',
+        Some('r') => '\r',
+        Some('b') => '\u{8}',
+        Some('f') => '\u{c}',
+        Some(character @ ('"' | '\\')) => character,
+        Some('/') if syntax == Syntax::Json => '/',
+        Some('u') => {
+            let code = hex_digits(characters, 4)?;
+            match code {
+                0xD800..=0xDBFF if syntax == Syntax::Json => {
+                    let low = match (characters.next(), characters.next()) {
+                        (Some('\\'), Some('u')) => hex_digits(characters, 4)?,
+                        _ => return Err(format!("unpaired surrogate '\\u{code:04x}'")),
+                    };
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        return Err(format!("unpaired surrogate '\\u{code:04x}'"));
+                    }
+                    let code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    char::from_u32(code).expect("surrogate pairs encode valid characters")
+                }
+                _ => char::from_u32(code)
+                    .ok_or_else(|| format!("invalid escape '\\u{code:04x}'"))?,
+            }
+        }
+        Some('U') if syntax == Syntax::Toml => {
+            let code = hex_digits(characters, 8)?;
+            char::from_u32(code).ok_or_else(|| format!("invalid escape '\\U{code:08x}'"))?
+        }
+        Some(character) => return Err(format!("invalid escape '\\{character}'")),
+        None => return Err("unterminated string".to_string()),
+    })
+}
+
+/// Reads the `count` hexadecimal digits of a `\u` or `\U` escape.
+fn hex_digits(characters: &mut Peekable<Chars<'_>>, count: usize) -> Result<u32, String> {
+    let digits: String = characters.by_ref().take(count).collect();
+    if digits.len() == count && digits.chars().all(|digit| digit.is_ascii_hexdigit()) {
+        Ok(u32::from_str_radix(&digits, 16).expect("the digits are checked"))
+    } else {
+        Err(format!("invalid escape with digits '{digits}'"))
+    }
+}
+
+fn parse_json(text: &str) -> Result<Tables, String> {
+    let mut characters = text.chars().peekable();
+    let mut line = 1;
+    let mut tables: Tables = vec![(String::new(), Vec::new())];
+    skip_blank(&mut characters, &mut line, false);
+    json_object(&mut characters, &mut line, |key, characters, line| {
+        if characters.peek() == Some(&'{') {
+            let mut values = Vec::new();
+            json_object(characters, line, |key, characters, line| {
+                if let Some(value) = json_value(characters, line)? {
+                    values.push((key, value));
+                }
+                Ok(())
+            })?;
+            tables.push((key, values));
+        } else if let Some(value) = json_value(characters, line)? {
+            tables[0].1.push((key, value));
+        }
+        Ok(())
+    })?;
+    skip_blank(&mut characters, &mut line, false);
+    match characters.next() {
+        None => Ok(tables),
+        Some(_) => Err(format!("unexpected text after the object on line {line}")),
+    }
+}
+
+/// Reads an object, calling `member` to read the value of each key.
+fn json_object<'a>(
+    characters: &mut Peekable<Chars<'a>>,
+    line: &mut usize,
+    mut member: impl FnMut(String, &mut Peekable<Chars<'a>>, &mut usize) -> Result<(), String>,
+) -> Result<(), String> {
+    if characters.next() != Some('{') {
+        return Err(format!("expected an object on line {line}"));
+    }
+    skip_blank(characters, line, false);
+    if characters.next_if_eq(&'}').is_some() {
+        return Ok(());
+    }
+    let mut keys = Vec::new();
+    loop {
+        skip_blank(characters, line, false);
+        if characters.next() != Some('"') {
+            return Err(format!("expected a key on line {line}"));
+        }
+        let key = quoted(characters, '"', Syntax::Json)
+            .map_err(|problem| format!("{problem} on line {line}"))?;
+        if keys.contains(&key) {
+            return Err(format!("\"{key}\" is defined twice on line {line}"));
+        }
+        keys.push(key.clone());
+        skip_blank(characters, line, false);
+        if characters.next() != Some(':') {
+            return Err(format!("expected ':' after \"{key}\" on line {line}"));
+        }
+        skip_blank(characters, line, false);
+        member(key, characters, line)?;
+        skip_blank(characters, line, false);
+        match characters.next() {
+            Some(',') => {}
+            Some('}') => return Ok(()),
+            _ => return Err(format!("expected ',' or '}}' on line {line}")),
+        }
+    }
+}
+
+/// Reads a value; `null` gives `None`, as if the key were missing.
+fn json_value(
+    characters: &mut Peekable<Chars<'_>>,
+    line: &mut usize,
+) -> Result<Option<ConfigValue>, String> {
+    match characters.peek() {
+        Some('"') => {
+            characters.next();
+            let text = quoted(characters, '"', Syntax::Json)
+                .map_err(|problem| format!("{problem} on line {line}"))?;
+            Ok(Some(ConfigValue::Text(text.into())))
+        }
+        Some('[') => {
+            characters.next();
+            let mut values = Vec::new();
+            skip_blank(characters, line, false);
+            if characters.next_if_eq(&']').is_some() {
+                return Ok(Some(ConfigValue::List(values)));
+            }
+            loop {
+                skip_blank(characters, line, false);
+                match json_value(characters, line)? {
+                    Some(ConfigValue::Bool(value)) => values.push(value.to_string().into()),
+                    Some(ConfigValue::Text(value)) => values.push(value),
+                    Some(ConfigValue::List(_)) | None => {
+                        return Err(format!("arrays may only hold plain values on line {line}"));
+                    }
+                }
+                skip_blank(characters, line, false);
+                match characters.next() {
+                    Some(',') => {}
+                    Some(']') => return Ok(Some(ConfigValue::List(values))),
+                    _ => return Err(format!("expected ',' or ']' on line {line}")),
+                }
+            }
+        }
+        Some('{') => Err(format!(
+            "objects are only allowed at the top level on line {line}"
+        )),
+        _ => {
+            let mut word = String::new();
+            while let Some(character) = characters.next_if(|character| {
+                character.is_ascii_alphanumeric() || matches!(character, '-' | '+' | '.')
+            }) {
+                word.push(character);
+            }
+            match word.as_str() {
+                "true" => Ok(Some(ConfigValue::Bool(true))),
+                "false" => Ok(Some(ConfigValue::Bool(false))),
+                "null" => Ok(None),
+                _ if is_json_number(&word) => Ok(Some(ConfigValue::Text(word.into()))),
+                _ => Err(format!("expected a value on line {line}")),
+            }
+        }
+    }
+}
+
+/// Whether a JSON bare value is a number: an optional `-`, an integer without
+/// leading zeros, and an optional fraction and exponent.
+fn is_json_number(word: &str) -> bool {
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|digit| digit.is_ascii_digit());
+    let unsigned = word.strip_prefix('-').unwrap_or(word);
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (unsigned, None),
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (mantissa, None),
+    };
+    digits(integer)
+        && (integer == "0" || !integer.starts_with('0'))
+        && fraction.iter().all(|fraction| digits(fraction))
+        && exponent
+            .iter()
+            .all(|exponent| digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)))
+}
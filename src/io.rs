@@ -0,0 +1,224 @@
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// A readable file, or standard input when given as `-`.
+///
+/// The file is opened while the command line is parsed, so a missing or
+/// unreadable file is reported like any other invalid value. Reads are
+/// buffered.
+///
+/// ```no_run
+/// use std::io::BufRead;
+///
+/// /// Count lines.
+/// #[fire::main]
+/// fn count(input: fire::Input) {
+///     println!("{}", input.lines().count());
+/// }
+/// ```
+pub struct Input {
+    path: Option<PathBuf>,
+    reader: Box<dyn BufRead + Send>,
+}
+
+impl Input {
+    /// Returns the path of the file, or `None` for standard input.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+}
+
+impl FromStr for Input {
+    type Err = OpenError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "-" {
+            return Ok(Input {
+                path: None,
+                reader: Box::new(BufReader::new(io::stdin())),
+            });
+        }
+        let file = File::open(value).map_err(|error| OpenError::new(value, error))?;
+        Ok(Input {
+            path: Some(PathBuf::from(value)),
+            reader: Box::new(BufReader::new(file)),
+        })
+    }
+}
+
+impl Read for Input {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        self.reader.read(buffer)
+    }
+}
+
+impl BufRead for Input {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.reader.fill_buf()
+    }
+
+    fn consume(&mut self, amount: usize) {
+        self.reader.consume(amount)
+    }
+}
+
+impl fmt::Debug for Input {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => formatter.debug_tuple("Input").field(path).finish(),
+            None => formatter.write_str("Input(<stdin>)"),
+        }
+    }
+}
+
+/// A writable file, or standard output when given as `-`.
+///
+/// The path is checked while the command line is parsed, so a missing
+/// directory or a file that cannot be written is reported like any other
+/// invalid value. The file itself is only created, or truncated if it exists,
+/// by the first write or flush, or by [`Output::open`], so an existing file is
+/// left untouched when the command line turns out to be wrong or the command
+/// is never run. Writes to files are buffered and flushed when the value is
+/// dropped; call [`Write::flush`] to observe errors.
+///
+/// ```no_run
+/// use std::io::Write;
+///
+/// /// Write a greeting.
+/// #[fire::main]
+/// fn greet(mut output: fire::Output) -> std::io::Result<()> {
+///     writeln!(output, "hello")?;
+///     output.flush()
+/// }
+/// ```
+pub struct Output {
+    path: Option<PathBuf>,
+    writer: Option<Box<dyn Write + Send>>,
+}
+
+impl Output {
+    /// Returns the path of the file, or `None` for standard output.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Creates the file, or truncates it if it exists, unless a write already
+    /// did. Calling it first reports an unwritable path before any work is
+    /// done. An output that is never opened leaves the file as it was.
+    pub fn open(&mut self) -> io::Result<()> {
+        self.writer().map(|_| ())
+    }
+
+    fn writer(&mut self) -> io::Result<&mut (dyn Write + Send)> {
+        let writer = match (self.writer.take(), &self.path) {
+            (Some(writer), _) => writer,
+            (None, None) => Box::new(io::stdout()),
+            (None, Some(path)) => {
+                let file = File::create(path).map_err(|error| {
+                    io::Error::new(
+                        error.kind(),
+                        OpenError {
+                            path: path.clone(),
+                            error,
+                        },
+                    )
+                })?;
+                Box::new(BufWriter::new(file))
+            }
+        };
+        Ok(&mut **self.writer.insert(writer))
+    }
+}
+
+impl FromStr for Output {
+    type Err = OpenError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "-" {
+            return Ok(Output {
+                path: None,
+                writer: None,
+            });
+        }
+        let path = PathBuf::from(value);
+        check_writable(&path).map_err(|error| OpenError::new(value, error))?;
+        Ok(Output {
+            path: Some(path),
+            writer: None,
+        })
+    }
+}
+
+/// Checks that `path` can be written without changing it: an existing file is
+/// opened for writing without truncating it, and a new one is created and
+/// removed again.
+fn check_writable(path: &Path) -> io::Result<()> {
+    match OpenOptions::new().write(true).open(path) {
+        Ok(_) => Ok(()),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(path)?;
+            fs::remove_file(path)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.writer()?.write(buffer)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer()?.flush()
+    }
+}
+
+impl fmt::Debug for Output {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(path) => formatter.debug_tuple("Output").field(path).finish(),
+            None => formatter.write_str("Output(<stdout>)"),
+        }
+    }
+}
+
+/// The error returned when an [`Input`] file cannot be opened or an [`Output`]
+/// path cannot be written, and the source of the error returned when an
+/// [`Output`] file cannot be created later on.
+#[derive(Debug)]
+pub struct OpenError {
+    path: PathBuf,
+    error: io::Error,
+}
+
+impl OpenError {
+    fn new(path: &str, error: io::Error) -> Self {
+        OpenError {
+            path: PathBuf::from(path),
+            error,
+        }
+    }
+}
+
+impl fmt::Display for OpenError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            formatter,
+            "cannot open {}: {}",
+            self.path.display(),
+            self.error
+        )
+    }
+}
+
+impl std::error::Error for OpenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
@@ -62,13 +62,128 @@
 //! |---|---|
 //! | `T` | Required `--name <VALUE>` option parsed with [`FromStr`](std::str::FromStr) |
 //! | `Option<T>` | Optional `--name <VALUE>` option |
-//! | `bool` | Value-less `--name` flag, defaulting to `false` |
+//! | `Option<Option<T>>` | Optional `--name` that may carry a value, but only as `--name=VALUE` |
+//! | `bool` | `--name` flag, defaulting to `false`; `--name=false` is also accepted |
+//! | `Option<bool>` | `--name` or `--no-name` flag, `None` when neither is given |
 //! | `&str` | Borrowed string option |
+//! | `PathBuf`, `OsString` | Option taking the argument exactly as the operating system passed it |
+//! | `&Path`, `&OsStr` | Borrowed forms of `PathBuf` and `OsString` |
+//! | `Vec<T>`, `&[T]` | Option that may be repeated, collecting every value in order |
+//! | [`Count`] | `-v`/`--verbose` flag counting its occurrences, as in `-vvv` |
+//! | `HashMap<K, V>`, `BTreeMap<K, V>` | Repeated `--name <KEY=VALUE>` option; each entry is split at its first `=`, and a key may be given once |
+//! | `(A, B, ...)` | Option taking comma-separated components, as in `--point 1,2` |
 //!
-//! Every non-string value is parsed through [`FromStr`](std::str::FromStr).
+//! Arguments are read with [`std::env::args_os`], so paths that are not valid
+//! UTF-8 reach `PathBuf` and `OsString` parameters untouched. Every other
+//! value must be UTF-8 and is parsed through [`FromStr`](std::str::FromStr).
+//! When parsing fails, the error names the expected type and includes the
+//! parser's own message if its error type implements
+//! [`Display`](std::fmt::Display).
 //! A parse failure, missing value, unknown option, or unknown command is
-//! reported on stderr together with the relevant usage line. CLI errors exit
-//! with status code 2.
+//! reported on stderr together with the relevant usage line. All problems
+//! with a command line are reported at once rather than one at a time. A
+//! mistyped command or option name also suggests the closest known name. CLI
+//! errors exit with status code 2.
+//!
+//! # Value types
+//!
+//! Integer parameters also accept `0x`, `0o`, and `0b` prefixes and `_`
+//! separators, as in `--mask 0xff` or `--limit 1_000_000`. For values that
+//! are awkward to type as plain numbers, this crate provides:
+//!
+//! | Type | Accepted values |
+//! |---|---|
+//! | [`Duration`] | `30s`, `5m`, `1h30m`, `250ms`, `1.5d` |
+//! | [`ByteSize`] | `512`, `64KiB`, `512MiB`, `1.5GB` |
+//!
+//! These types, like [`Count`], [`Input`], and [`Output`], are recognized by
+//! their path, so they are written as `fire::Duration` and so on rather than
+//! imported. Their help entries show example values:
+//!
+//! ```no_run
+//! #[fire::main]
+//! fn fetch(url: String, timeout: fire::Duration, max_size: Option<fire::ByteSize>) {
+//!     let timeout: std::time::Duration = timeout.into();
+//! }
+//! ```
+//!
+//! [`Input`] and [`Output`] take a path, or `-` for standard input or output,
+//! and show up as `<FILE|->` in help. An input file is opened while the
+//! command line is parsed, so a missing input is reported like an invalid
+//! value. An output path is checked as well, so a missing directory or an
+//! unwritable file is reported the same way, but the file is only created by
+//! the first write, so a command line that fails leaves an existing file
+//! untouched:
+//!
+//! ```no_run
+//! use std::io::{BufRead, Write};
+//!
+//! /// Copy non-empty lines.
+//! #[fire::main]
+//! fn strip(input: fire::Input, mut output: fire::Output) -> std::io::Result<()> {
+//!     for line in input.lines() {
+//!         let line = line?;
+//!         if !line.is_empty() {
+//!             writeln!(output, "{line}")?;
+//!         }
+//!     }
+//!     output.flush()
+//! }
+//! ```
+//!
+//! # Parameter attributes
+//!
+//! An option that is not a `Vec<T>` or `&[T]` may be given only once;
+//! `--name a --name b` or a repeated flag is an error naming both occurrences.
+//! `#[fire(...)]` on a parameter adjusts this and other parsing behavior:
+//!
+//! | Attribute | Effect |
+//! |---|---|
+//! | `#[fire(overrides)]` | A repeated option replaces the earlier value |
+//! | `#[fire(parse_with = f)]` | Parse the value with `fn(&str) -> Result<T, E>` instead of `FromStr` |
+//! | `#[fire(validate = f)]` | Check the parsed value with `fn(&T) -> bool` or `fn(&T) -> Result<(), E>` |
+//! | `#[fire(default = true)]` | Turn a `bool` flag on unless `--no-name` is given |
+//! | `#[fire(count)]` | Make an integer parameter a counted flag, like [`Count`] |
+//! | `#[fire(conflicts_with = other)]` | Reject the option when parameter `other` is also given |
+//! | `#[fire(requires = other)]` | Require parameter `other` whenever the option is given |
+//! | `#[fire(group = name)]` | Allow at most one option of the group |
+//! | `#[fire(group(name, required, multiple))]` | Also require one option of the group, or allow several |
+//! | `#[fire(env = "NAME")]` | Read the value from environment variable `NAME` when the option is not given |
+//! | `#[fire(prompt)]` | Ask for a missing required option on a terminal |
+//! | `#[fire(secret)]` | Keep the value out of error messages and `--fire-print-config`, and read it without echo when prompting |
+//!
+//! Errors returned by `parse_with` and `validate` functions are reported like
+//! `FromStr` failures:
+//!
+//! ```no_run
+//! use std::time::Duration;
+//!
+//! fn seconds(value: &str) -> Result<Duration, std::num::ParseIntError> {
+//!     value.trim_end_matches('s').parse().map(Duration::from_secs)
+//! }
+//!
+//! fn is_unprivileged(port: &u16) -> bool {
+//!     *port >= 1024
+//! }
+//!
+//! #[fire::main]
+//! fn serve(
+//!     #[fire(validate = is_unprivileged)] port: u16,
+//!     #[fire(parse_with = seconds)] timeout: Duration,
+//! ) {}
+//! ```
+//!
+//! Groups are shown together in the usage line, so the following command is
+//! used as `export (--json | --csv) [--delimiter <DELIMITER>]`:
+//!
+//! ```no_run
+//! #[fire::main]
+//! fn export(
+//!     #[fire(group(format, required))] json: bool,
+//!     #[fire(group = format)] csv: bool,
+//!     #[fire(requires = csv)] delimiter: Option<char>,
+//! ) {}
+//! ```
 //!
 //! # Generated help
 //!
@@ -81,14 +196,30 @@
 //! Usage: app --name <NAME> [--excited]
 //!
 //! Options:
-//!     --name <NAME>    Person to welcome.
-//!     --excited        Add an exclamation mark.
-//!     -h, --help       Print help
+//!     --name <NAME>     Person to welcome.
+//!     --excited         Add an exclamation mark.
+//!     --color <WHEN>    Color output: auto, always, or never
+//!     -h, --help        Print help
 //! ```
 //!
+//! `-h` prints a summary built from the first paragraph of each documentation
+//! comment, while `--help` prints the comments in full. Command lists always use
+//! the first paragraph.
+//!
+//! Documentation is written in Markdown, as for rustdoc, and rendered as plain
+//! text: code ticks and emphasis markers are removed, links show their URL, and
+//! list items get bullets. An `# Examples` section is moved into a dedicated
+//! `Examples:` block at the end of `--help`.
+//!
 //! Module applications additionally support `app --help` to list commands and
 //! `app <COMMAND> --help` to describe one command.
 //!
+//! Help and the `error:` prefix are styled with ANSI colors when written to a
+//! terminal. Setting `NO_COLOR` disables colors and `CLICOLOR_FORCE` forces
+//! them; the built-in `--color <auto|always|never>` option overrides both. A
+//! command that declares its own `color` parameter does not get the built-in
+//! option.
+//!
 //! # Fallible commands
 //!
 //! Commands may return `Result`. An error is formatted through
@@ -122,531 +253,139 @@
 //! The same argument works on modules; async and synchronous commands can be
 //! mixed in one module.
 //!
+//! # Abbreviations
+//!
+//! `#[fire::main(abbrev)]` accepts any unique prefix of a command or long
+//! option name, so `app dep --verb` runs `deploy --verbose`. A prefix shared by
+//! several names is reported as ambiguous together with the candidates.
+//! Attribute options can be combined, as in `#[fire::main(tokio, abbrev)]`.
+//!
+//! # Response files
+//!
+//! An argument of the form `@path` is replaced with the arguments read from
+//! that file, which helps when a command line would exceed the limits of the
+//! shell. Arguments in the file are separated by whitespace or newlines and
+//! quoted like in a shell, with `'...'`, `"..."`, or a backslash; a `#` starts
+//! a comment. On Unix, arguments need not be valid UTF-8, just like on the
+//! command line. Response files may refer to further response files, up to
+//! ten levels deep.
+//!
+//! Where `@` starts legitimate values, turn this off for the command with
+//! `#[fire(no_response_files)]` below `#[fire::main]`, or on the function of a
+//! command module:
+//!
+//! ```no_run
+//! #[fire::main]
+//! #[fire(no_response_files)]
+//! fn mention(handle: String) {}
+//! ```
+//!
+//! # Configuration files
+//!
+//! `#[fire::main(config = "app.toml")]` reads defaults for any option from a
+//! configuration file: the given path if it exists, or the file named with the
+//! built-in `--config <PATH>` option. Keys are option names, and values are
+//! parsed like values on the command line. In a command module, a table named
+//! after a command, such as `[deploy]`, applies to that command only, while
+//! top-level keys are shared by all commands:
+//!
+//! ```toml
+//! port = 8080
+//!
+//! [deploy]
+//! target = "staging"
+//! tags = ["web", "eu"]
+//! force = true
+//! ```
+//!
+//! Files ending in `.json` hold the same structure as a JSON object. Only the
+//! part of TOML and JSON that option values need is supported: strings,
+//! numbers, booleans, and arrays of those, at the top level or in a table per
+//! command. Multi-line strings, dates, dotted keys, nested or inline tables,
+//! and keys defined twice are reported as errors rather than misread, and so
+//! are keys and tables that name no option or command, with the closest name
+//! as a suggestion.
+//!
+//! A value given on the command line wins over the environment variable of an
+//! `#[fire(env = "NAME")]` parameter, which wins over the configuration file,
+//! which wins over the default. `--fire-print-config` shows the effective value
+//! of every option and where it came from instead of running the command.
+//!
+//! Values from the environment or a configuration file are defaults, so
+//! `conflicts_with` and exclusive groups drop them in favor of a conflicting
+//! option given on the command line, and `requires` only checks options given
+//! on the command line. `json = true` in the file and `--csv` on the command
+//! line thus run with `--csv` alone.
+//!
+//! # Prompting
+//!
+//! With `#[fire::main(prompt)]`, or `#[fire(prompt)]` on a single parameter, a
+//! missing required option is asked for instead of reported when both
+//! standard input and standard error are terminals. The question is the first
+//! paragraph of the parameter's documentation, and an answer that does not
+//! parse is reported and asked for again. Types implementing [`Choices`] are
+//! offered as a numbered menu whose names are then parsed with `FromStr`, so
+//! they must be spellings the type accepts. `#[fire(secret)]` values are typed
+//! without echo. Nothing is asked when the command line has
+//! other problems, or when the application is not run interactively, so
+//! scripts see the usual error.
+//!
+//! # Confirmation
+//!
+//! `#[fire(confirm = "...")]` below `#[fire::main]`, or on the function of a
+//! command module, asks the question and waits for `y` before a destructive
+//! command runs. The built-in `--yes`/`-y` flag skips the question, and is
+//! required when the application is not run on a terminal. The question is
+//! asked once every required option is given, but before any value is
+//! converted, so a declined command has not opened any [`Input`] or other
+//! file:
+//!
+//! ```no_run
+//! #[fire::main]
+//! mod cli {
+//!     #[fire(confirm = "This deletes all data. Continue?")]
+//!     pub fn drop_database(name: String) {}
+//! }
+//! ```
+//!
+//! # Interactive shell
+//!
+//! `#[fire::main(repl)]` on a command module adds a `shell` command that reads
+//! command lines one at a time, quoted like in a shell, and runs them as if
+//! each had been given to the application. An error is printed and the
+//! session continues. Besides the commands of the module, the shell
+//! understands `help [COMMAND]`, `history`, `exit`, and `quit`, so a module
+//! with `repl` cannot have commands by those names. Commands run in the same
+//! process, so state kept in statics lasts for the whole session; there is no
+//! hook that runs once when the shell starts, so such state is set up lazily,
+//! for example with [`std::sync::OnceLock`].
+//!
+//! # Batch mode
+//!
+//! Command module applications accept `--fire-batch <FILE|->`, which runs
+//! every line of a file, or of standard input for `-`, as one command line.
+//! Lines are quoted like response files, and as in those, arguments need not
+//! be UTF-8 on Unix. `#` starts a comment. Failing lines are reported with their
+//! line number and the remaining lines still run, unless a `set -e` line came
+//! before them, in which case the first failure stops the batch; `set +e`
+//! switches back. The application fails if any line did.
+//!
+//! ```text
+//! # provision.txt
+//! set -e
+//! create-user --name alice
+//! grant --user alice --role admin
+//! ```
+//!
 //! # Current limitations
 //!
 //! - Command modules must be inline modules.
 //! - Methods and generic functions are not supported.
 //! - Async functions require `#[fire::main(tokio)]`.
-//! - Parameters are named options; positional arguments and short option names
-//!   are not currently supported.
-//! - Parameter attributes other than documentation comments are rejected.
-
-use proc_macro::TokenStream;
-use proc_macro2::{Ident, TokenStream as TokenStream2};
-use quote::{format_ident, quote};
-use syn::{
-    parse_macro_input, Attribute, Expr, FnArg, Item, ItemFn, ItemMod, Lit, Meta, Pat, ReturnType,
-    Type,
-};
-
-struct Argument {
-    ident: Ident,
-    ty: Type,
-    cli_name: String,
-    description: String,
-    kind: ArgumentKind,
-}
-
-#[derive(Clone, Copy)]
-enum ArgumentKind {
-    Required,
-    Optional,
-    Flag,
-}
-
-fn kebab_case(name: &str) -> String {
-    name.replace('_', "-")
-}
-
-fn inner_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
-    let Type::Path(path) = ty else {
-        return None;
-    };
-    let segment = path.path.segments.last()?;
-    if segment.ident != wrapper {
-        return None;
-    }
-    let syn::PathArguments::AngleBracketed(arguments) = &segment.arguments else {
-        return None;
-    };
-    match arguments.args.first()? {
-        syn::GenericArgument::Type(ty) => Some(ty),
-        _ => None,
-    }
-}
-
-fn is_bool(ty: &Type) -> bool {
-    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
-}
-
-fn is_str_reference(ty: &Type) -> bool {
-    matches!(ty, Type::Reference(reference)
-        if matches!(&*reference.elem, Type::Path(path) if path.path.is_ident("str")))
-}
-
-fn documentation(attributes: &[Attribute]) -> String {
-    attributes
-        .iter()
-        .filter_map(|attribute| {
-            if !attribute.path().is_ident("doc") {
-                return None;
-            }
-            let Meta::NameValue(meta) = &attribute.meta else {
-                return None;
-            };
-            let Expr::Lit(expression) = &meta.value else {
-                return None;
-            };
-            let Lit::Str(text) = &expression.lit else {
-                return None;
-            };
-            Some(text.value().trim().to_string())
-        })
-        .collect::<Vec<_>>()
-        .join("\n")
-}
-
-fn arguments(function: &mut ItemFn) -> syn::Result<Vec<Argument>> {
-    function
-        .sig
-        .inputs
-        .iter_mut()
-        .map(|input| {
-            let FnArg::Typed(input) = input else {
-                return Err(syn::Error::new_spanned(
-                    input,
-                    "methods cannot be CLI commands",
-                ));
-            };
-            let Pat::Ident(pattern) = &*input.pat else {
-                return Err(syn::Error::new_spanned(
-                    &input.pat,
-                    "command parameters must be identifiers",
-                ));
-            };
-            if let Some(attribute) = input
-                .attrs
-                .iter()
-                .find(|attribute| !attribute.path().is_ident("doc"))
-            {
-                return Err(syn::Error::new_spanned(
-                    attribute,
-                    "only documentation comments are supported on parameters",
-                ));
-            }
-
-            let kind = if is_bool(&input.ty) {
-                ArgumentKind::Flag
-            } else if inner_type(&input.ty, "Option").is_some() {
-                ArgumentKind::Optional
-            } else {
-                ArgumentKind::Required
-            };
-
-            let description = documentation(&input.attrs);
-            input.attrs.clear();
-
-            Ok(Argument {
-                ident: pattern.ident.clone(),
-                ty: (*input.ty).clone(),
-                cli_name: kebab_case(&pattern.ident.to_string()),
-                description,
-                kind,
-            })
-        })
-        .collect()
-}
-
-fn command_help(function: &ItemFn, arguments: &[Argument], command_name: &str) -> String {
-    let mut help = String::new();
-    let description = documentation(&function.attrs);
-    if !description.is_empty() {
-        help.push_str(&description);
-        help.push_str("\n\n");
-    }
-
-    help.push_str("Usage: {program}");
-    if !command_name.is_empty() {
-        help.push(' ');
-        help.push_str(command_name);
-    }
-    for argument in arguments {
-        let option = match argument.kind {
-            ArgumentKind::Required => format!(
-                " --{} <{}>",
-                argument.cli_name,
-                argument.cli_name.replace('-', "_").to_uppercase()
-            ),
-            ArgumentKind::Optional => format!(
-                " [--{} <{}>]",
-                argument.cli_name,
-                argument.cli_name.replace('-', "_").to_uppercase()
-            ),
-            ArgumentKind::Flag => format!(" [--{}]", argument.cli_name),
-        };
-        help.push_str(&option);
-    }
-    help.push_str("\n\nOptions:\n");
-    for argument in arguments {
-        let option = match argument.kind {
-            ArgumentKind::Flag => format!("    --{}", argument.cli_name),
-            _ => format!(
-                "    --{} <{}>",
-                argument.cli_name,
-                argument.cli_name.replace('-', "_").to_uppercase()
-            ),
-        };
-        help.push_str(&option);
-        if !argument.description.is_empty() {
-            help.push_str("    ");
-            help.push_str(&argument.description.replace('\n', " "));
-        }
-        help.push('\n');
-    }
-    help.push_str("    -h, --help    Print help");
-    help
-}
-
-fn program_name() -> TokenStream2 {
-    quote! {
-        std::env::args()
-            .next()
-            .and_then(|path| {
-                std::path::Path::new(&path)
-                    .file_name()
-                    .map(|name| name.to_string_lossy().into_owned())
-            })
-            .unwrap_or_else(|| "app".to_string())
-    }
-}
-
-fn parsed_value(value: TokenStream2, ty: &Type, cli_name: &str) -> TokenStream2 {
-    if is_str_reference(ty) {
-        quote! { #value.as_str() }
-    } else {
-        quote! {
-            #value.parse::<#ty>().map_err(|_| {
-                __fire_error(format!("invalid value for '--{}': '{}'", #cli_name, #value))
-            })?
-        }
-    }
-}
-
-fn command_runner(
-    function: &mut ItemFn,
-    runner_name: &Ident,
-    visibility: TokenStream2,
-    command_name: &str,
-    tokio: bool,
-) -> syn::Result<TokenStream2> {
-    if function.sig.asyncness.is_some() && !tokio {
-        return Err(syn::Error::new_spanned(
-            function.sig.asyncness,
-            "async commands require a runtime; use #[fire::main(tokio)]",
-        ));
-    }
-    if !function.sig.generics.params.is_empty() {
-        return Err(syn::Error::new_spanned(
-            &function.sig.generics,
-            "generic commands are not supported",
-        ));
-    }
-
-    let arguments = arguments(function)?;
-    let function_name = &function.sig.ident;
-    let help = command_help(function, &arguments, command_name);
-    let usage = help
-        .lines()
-        .find(|line| line.starts_with("Usage:"))
-        .expect("command help always contains usage")
-        .to_string();
-    let program_name = program_name();
-
-    let storage = arguments.iter().map(|argument| {
-        let storage_name = format_ident!("__fire_value_{}", argument.ident);
-        quote! { let mut #storage_name: Option<String> = None; }
-    });
-
-    let option_matches = arguments.iter().map(|argument| {
-        let storage_name = format_ident!("__fire_value_{}", argument.ident);
-        let cli_name = &argument.cli_name;
-        match argument.kind {
-            ArgumentKind::Flag => quote! {
-                if __fire_key == concat!("--", #cli_name) {
-                    if __fire_inline_value.is_some() {
-                        return Err(__fire_error(format!("flag '--{}' does not take a value", #cli_name)));
-                    }
-                    #storage_name = Some("true".to_string());
-                    __fire_matched = true;
-                }
-            },
-            ArgumentKind::Required | ArgumentKind::Optional => quote! {
-                if __fire_key == concat!("--", #cli_name) {
-                    let value = match __fire_inline_value {
-                        Some(value) => value.to_string(),
-                        None => {
-                            __fire_index += 1;
-                            let value = __fire_args.get(__fire_index).cloned().ok_or_else(|| {
-                                __fire_error(format!("option '--{}' requires a value", #cli_name))
-                            })?;
-                            if value.starts_with("--") || value == "-h" {
-                                return Err(__fire_error(format!(
-                                    "option '--{}' requires a value",
-                                    #cli_name
-                                )));
-                            }
-                            value
-                        }
-                    };
-                    #storage_name = Some(value);
-                    __fire_matched = true;
-                }
-            },
-        }
-    });
-
-    let conversions = arguments.iter().map(|argument| {
-        let ident = &argument.ident;
-        let ty = &argument.ty;
-        let storage_name = format_ident!("__fire_value_{}", ident);
-        let cli_name = &argument.cli_name;
-        match argument.kind {
-            ArgumentKind::Flag => quote! {
-                let #ident: bool = #storage_name.is_some();
-            },
-            ArgumentKind::Optional => {
-                let inner = inner_type(ty, "Option").expect("optional type checked above");
-                if is_str_reference(inner) {
-                    quote! { let #ident: #ty = #storage_name.as_deref(); }
-                } else {
-                    let parsed = parsed_value(quote! { value }, inner, cli_name);
-                    quote! {
-                        let #ident: #ty = match #storage_name.as_ref() {
-                            Some(value) => Some(#parsed),
-                            None => None,
-                        };
-                    }
-                }
-            }
-            ArgumentKind::Required => {
-                if is_str_reference(ty) {
-                    quote! {
-                        let #ident: #ty = #storage_name.as_deref().ok_or_else(|| {
-                            __fire_error(format!("missing required option '--{}'", #cli_name))
-                        })?;
-                    }
-                } else {
-                    let parsed = parsed_value(quote! { value }, ty, cli_name);
-                    quote! {
-                        let #ident: #ty = {
-                            let value = #storage_name.as_ref().ok_or_else(|| {
-                                __fire_error(format!("missing required option '--{}'", #cli_name))
-                            })?;
-                            #parsed
-                        };
-                    }
-                }
-            }
-        }
-    });
-
-    let call_arguments = arguments.iter().map(|argument| &argument.ident);
-    let mut invocation = quote! { #function_name(#(#call_arguments),*) };
-    if function.sig.asyncness.is_some() {
-        invocation = quote! {
-            ::tokio::runtime::Builder::new_multi_thread()
-                .enable_all()
-                .build()
-                .expect("failed to build tokio runtime")
-                .block_on(#invocation)
-        };
-    }
-    let call = match &function.sig.output {
-        ReturnType::Type(_, ty) if inner_type(ty, "Result").is_some() => quote! {
-            #invocation
-                .map(|_| None)
-                .map_err(|error| error.to_string())
-        },
-        _ => quote! {
-            #invocation;
-            Ok(None)
-        },
-    };
-
-    Ok(quote! {
-        #[doc(hidden)]
-        #visibility fn #runner_name<I, S>(input: I) -> Result<Option<String>, String>
-        where
-            I: IntoIterator<Item = S>,
-            S: Into<String>,
-        {
-            let __fire_args: Vec<String> = input.into_iter().map(Into::into).collect();
-            let program = #program_name;
-            let __fire_help = #help.replace("{program}", &program);
-            let __fire_usage = #usage.replace("{program}", &program);
-            if __fire_args.iter().any(|argument| argument == "--help" || argument == "-h") {
-                return Ok(Some(__fire_help));
-            }
-            let __fire_error = |message: String| {
-                format!(
-                    "{}\n\n{}\n\nFor more information, try '--help'.",
-                    message, __fire_usage
-                )
-            };
-            #(#storage)*
-
-            let mut __fire_index = 0usize;
-            while __fire_index < __fire_args.len() {
-                let __fire_raw = &__fire_args[__fire_index];
-                let (__fire_key, __fire_inline_value) = match __fire_raw.split_once('=') {
-                    Some((key, value)) => (key, Some(value)),
-                    None => (__fire_raw.as_str(), None),
-                };
-                let mut __fire_matched = false;
-                #(#option_matches)*
-                if !__fire_matched {
-                    return Err(__fire_error(format!("unexpected argument '{}'", __fire_raw)));
-                }
-                __fire_index += 1;
-            }
-
-            #(#conversions)*
-            #call
-        }
-    })
-}
-
-fn entrypoint(call: TokenStream2) -> TokenStream2 {
-    quote! {
-        fn main() {
-            match #call {
-                Ok(Some(help)) => println!("{}", help),
-                Ok(None) => {}
-                Err(error) => {
-                    eprintln!("error: {}", error);
-                    std::process::exit(2);
-                }
-            }
-        }
-    }
-}
-
-fn expand_function(mut function: ItemFn, tokio: bool) -> syn::Result<TokenStream2> {
-    if function.sig.ident == "main" {
-        return Err(syn::Error::new_spanned(
-            &function.sig.ident,
-            "put #[fire::main] on the command function, not on a function named `main`",
-        ));
-    }
-    let runner_name = format_ident!("__fire_run_{}", function.sig.ident);
-    let runner = command_runner(&mut function, &runner_name, quote! { pub(crate) }, "", tokio)?;
-    let main = entrypoint(quote! { #runner_name(std::env::args().skip(1)) });
-    Ok(quote! { #function #runner #main })
-}
-
-fn expand_module(mut module: ItemMod, tokio: bool) -> syn::Result<TokenStream2> {
-    let module_name = module.ident.clone();
-    let module_description = documentation(&module.attrs);
-    let Some((_, items)) = &mut module.content else {
-        return Err(syn::Error::new_spanned(
-            &module,
-            "#[fire::main] requires an inline module",
-        ));
-    };
-
-    let mut commands = Vec::new();
-    let mut runners = Vec::new();
-    for item in items.iter_mut() {
-        let Item::Fn(function) = item else {
-            continue;
-        };
-        let command_name = kebab_case(&function.sig.ident.to_string());
-        let runner_name = format_ident!("__fire_run_{}", function.sig.ident);
-        runners.push(command_runner(
-            function,
-            &runner_name,
-            quote! {},
-            &command_name,
-            tokio,
-        )?);
-        commands.push((command_name, runner_name));
-    }
-
-    for runner in runners {
-        items.push(syn::parse2(runner).expect("generated command runner"));
-    }
-    let dispatch = commands.iter().map(|(command_name, runner_name)| {
-        quote! { #command_name => #runner_name(arguments), }
-    });
-    let mut root_help = String::new();
-    if !module_description.is_empty() {
-        root_help.push_str(&module_description);
-        root_help.push_str("\n\n");
-    }
-    root_help.push_str("Usage: {program} <COMMAND>\n\nCommands:\n");
-    for (command_name, _) in &commands {
-        let description = items
-            .iter()
-            .find_map(|item| match item {
-                Item::Fn(function)
-                    if kebab_case(&function.sig.ident.to_string()) == *command_name =>
-                {
-                    Some(documentation(&function.attrs))
-                }
-                _ => None,
-            })
-            .unwrap_or_default();
-        root_help.push_str(&format!("    {command_name}"));
-        if !description.is_empty() {
-            root_help.push_str("    ");
-            root_help.push_str(&description.replace('\n', " "));
-        }
-        root_help.push('\n');
-    }
-    root_help.push_str("\nOptions:\n    -h, --help    Print help");
-    let root_usage = root_help
-        .lines()
-        .find(|line| line.starts_with("Usage:"))
-        .expect("root help always contains usage")
-        .to_string();
-    let program_name = program_name();
-    items.push(
-        syn::parse2(quote! {
-            #[doc(hidden)]
-            pub(crate) fn __fire_run<I, S>(input: I) -> Result<Option<String>, String>
-            where
-                I: IntoIterator<Item = S>,
-            S: Into<String>,
-            {
-                let mut input = input.into_iter().map(Into::into);
-                let program = #program_name;
-                let __fire_help = #root_help.replace("{program}", &program);
-                let __fire_usage = #root_usage.replace("{program}", &program);
-                let __fire_error = |message: String| {
-                    format!(
-                        "{}\n\n{}\n\nFor more information, try '--help'.",
-                        message, __fire_usage
-                    )
-                };
-                let command = input
-                    .next()
-                    .ok_or_else(|| __fire_error("missing command".to_string()))?;
-                if command == "--help" || command == "-h" {
-                    return Ok(Some(__fire_help));
-                }
-                let arguments: Vec<String> = input.collect();
-                match command.as_str() {
-                    #(#dispatch)*
-                    _ => Err(__fire_error(format!("unknown command '{}'", command))),
-                }
-            }
-        })
-        .expect("generated command dispatcher"),
-    );
-
-    let main = entrypoint(quote! { #module_name::__fire_run(std::env::args().skip(1)) });
-    Ok(quote! { #module #main })
-}
+//! - Parameters are named options; positional arguments are not currently
+//!   supported, and only counted flags have a short name.
+//! - Parameter attributes other than documentation comments and `#[fire(...)]`
+//!   are rejected.
 
 /// Turns a function or inline module into a complete command-line application.
 ///
@@ -658,8 +397,10 @@ fn expand_module(mut module: ItemMod, tokio: bool) -> syn::Result<TokenStream2>
 ///
 /// - `T` is a required named option;
 /// - `Option<T>` is an optional named option;
-/// - `bool` is a value-less flag;
-/// - `&str` borrows its value for the duration of the command call.
+/// - `bool` is a flag, and `Option<bool>` a flag that can also be negated;
+/// - `Vec<T>` and `&[T]` are options that may be repeated;
+/// - `&str`, `&Path`, and `&OsStr` borrow their value for the duration of the
+///   command call.
 ///
 /// Documentation comments on the target and its parameters are included in
 /// the generated `-h`/`--help` output. See the [crate-level documentation](crate)
@@ -703,32 +444,42 @@ fn expand_module(mut module: ItemMod, tokio: bool) -> syn::Result<TokenStream2>
 /// #[fire::main(tokio)]
 /// async fn fetch(url: String) {}
 /// ```
-#[proc_macro_attribute]
-pub fn main(metadata: TokenStream, input: TokenStream) -> TokenStream {
-    let tokio = if metadata.is_empty() {
-        false
-    } else {
-        let runtime = parse_macro_input!(metadata as Ident);
-        if runtime != "tokio" {
-            return syn::Error::new_spanned(
-                &runtime,
-                format!("unsupported runtime `{runtime}`; only `tokio` is supported"),
-            )
-            .into_compile_error()
-            .into();
-        }
-        true
-    };
-    let item = parse_macro_input!(input as Item);
-    let expanded = match item {
-        Item::Fn(function) => expand_function(function, tokio),
-        Item::Mod(module) => expand_module(module, tokio),
-        other => Err(syn::Error::new_spanned(
-            other,
-            "#[fire::main] only supports functions and inline modules",
-        )),
+///
+/// # Abbreviations
+///
+/// `#[fire::main(abbrev)]` accepts unique prefixes of command and option names.
+///
+/// # Configuration files
+///
+/// `#[fire::main(config = "app.toml")]` reads option defaults from a TOML or
+/// JSON file, which `--config <PATH>` can replace.
+///
+/// # Prompting
+///
+/// `#[fire::main(prompt)]` asks for missing required options on a terminal.
+///
+/// # Interactive shell
+///
+/// `#[fire::main(repl)]` adds a `shell` command to a command module.
+pub use fire_macros::main;
+
+mod config;
+mod io;
+mod private;
+mod prompt;
+mod shell;
+mod values;
+
+pub use io::{Input, OpenError, Output};
+pub use prompt::Choices;
+pub use values::{ByteSize, Count, Duration, ValueError};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::config::{fallback, Config, ConfigValue, Fallback};
+    pub use crate::private::*;
+    pub use crate::prompt::{
+        ask, confirm, confirm_with, interactive, prompt, Offer, ViaChoices, WithoutChoices,
     };
-    expanded
-        .unwrap_or_else(syn::Error::into_compile_error)
-        .into()
+    pub use crate::shell::{batch, run_batch, run_shell, shell};
 }
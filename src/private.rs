@@ -0,0 +1,331 @@
+//! Runtime support for code generated by `#[fire::main]`. Not public API.
+
+use std::ffi::{OsStr, OsString};
+use std::num::ParseIntError;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Primitive integers, parsed through their `from_str_radix`.
+pub trait Integer: Sized {
+    fn from_str_radix(value: &str, radix: u32) -> Result<Self, ParseIntError>;
+
+    /// Converts the number of times a counted flag was given, stopping at the
+    /// largest value of the type.
+    fn from_count(count: u8) -> Self;
+}
+
+macro_rules! integer {
+    ($($ty:ty),*) => {
+        $(
+            impl Integer for $ty {
+                fn from_str_radix(value: &str, radix: u32) -> Result<Self, ParseIntError> {
+                    <$ty>::from_str_radix(value, radix)
+                }
+
+                fn from_count(count: u8) -> Self {
+                    <$ty>::try_from(count).unwrap_or(<$ty>::MAX)
+                }
+            }
+        )*
+    };
+}
+
+integer!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+/// Parses an integer that may use a `0x`, `0o`, or `0b` prefix and `_`
+/// separators between digits, such as `0xff`, `-0b1010`, or `1_000_000`.
+pub fn parse_integer<T: Integer>(value: &str) -> Result<T, ParseIntError> {
+    let (sign, unsigned) = match value.strip_prefix(['-', '+']) {
+        Some(rest) => (&value[..1], rest),
+        None => ("", value),
+    };
+    let prefix = unsigned.get(..2).map(str::to_ascii_lowercase);
+    let (radix, digits) = match prefix.as_deref() {
+        Some("0x") => (16, &unsigned[2..]),
+        Some("0o") => (8, &unsigned[2..]),
+        Some("0b") => (2, &unsigned[2..]),
+        _ => (10, unsigned),
+    };
+    // A separator is only allowed between digits, so `_1`, `1_`, and `0x_1`
+    // keep the underscore and fail to parse.
+    let separated = !digits.starts_with('_') && !digits.ends_with('_');
+    let digits = if separated {
+        digits.replace('_', "")
+    } else {
+        digits.to_string()
+    };
+    T::from_str_radix(&format!("{sign}{digits}"), radix)
+}
+
+/// Finds the candidate closest to `value`, if any is close enough to be a
+/// likely typo.
+pub fn suggestion(value: &str, candidates: &[&'static str]) -> Option<&'static str> {
+    candidates
+        .iter()
+        .map(|candidate| (distance(value, candidate), *candidate))
+        .filter(|(distance, candidate)| *distance <= (candidate.chars().count() / 3).max(1))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// The number of edits between two strings, where swapping two adjacent
+/// characters is a single edit.
+fn distance(left: &str, right: &str) -> usize {
+    let left: Vec<char> = left.chars().collect();
+    let right: Vec<char> = right.chars().collect();
+    let mut table = vec![vec![0usize; right.len() + 1]; left.len() + 1];
+    for (i, row) in table.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in table[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=left.len() {
+        for j in 1..=right.len() {
+            let cost = usize::from(left[i - 1] != right[j - 1]);
+            let mut best = (table[i - 1][j] + 1)
+                .min(table[i][j - 1] + 1)
+                .min(table[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && left[i - 1] == right[j - 2] && left[i - 2] == right[j - 1] {
+                best = best.min(table[i - 2][j - 2] + 1);
+            }
+            table[i][j] = best;
+        }
+    }
+    table[left.len()][right.len()]
+}
+
+/// Wraps a parse error to describe it through `Display` when its type
+/// implements it. Method resolution prefers the `Display` implementation on
+/// `Describe` over the fallback on `&Describe`.
+pub struct Describe<'a, T>(pub &'a T);
+
+pub trait ViaDisplay {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T: std::fmt::Display> ViaDisplay for Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        Some(self.0.to_string())
+    }
+}
+
+pub trait ViaFallback {
+    fn describe(&self) -> Option<String>;
+}
+
+impl<T> ViaFallback for &Describe<'_, T> {
+    fn describe(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The result of a `validate` function: `false` or an error rejects the
+/// value, with the error as the reason.
+pub trait Validation {
+    /// The reason the value was rejected, which is empty for `false`.
+    fn problem(self) -> Option<String>;
+}
+
+impl Validation for bool {
+    fn problem(self) -> Option<String> {
+        (!self).then(String::new)
+    }
+}
+
+impl<E: std::fmt::Display> Validation for Result<(), E> {
+    fn problem(self) -> Option<String> {
+        self.err().map(|error| error.to_string())
+    }
+}
+
+/// Splits `--key=value` at the first `=`. The key is only ever compared with
+/// option names, so it is converted lossily; the value stays an OS string so
+/// that paths reach the command untouched.
+pub fn split_option(argument: &OsStr) -> (String, Option<OsString>) {
+    let bytes = argument.as_encoded_bytes();
+    match bytes.iter().position(|byte| *byte == b'=') {
+        Some(position) => {
+            // SAFETY: both halves border an ASCII `=`, which is a valid split
+            // point of the platform encoding.
+            let (key, value) = unsafe {
+                (
+                    OsStr::from_encoded_bytes_unchecked(&bytes[..position]),
+                    OsStr::from_encoded_bytes_unchecked(&bytes[position + 1..]),
+                )
+            };
+            (
+                key.to_string_lossy().into_owned(),
+                Some(value.to_os_string()),
+            )
+        }
+        None => (argument.to_string_lossy().into_owned(), None),
+    }
+}
+
+/// How deeply response files may include other response files.
+const RESPONSE_FILE_DEPTH: usize = 10;
+
+/// Replaces every `@path` argument with the arguments read from that file,
+/// including `@path` arguments inside it.
+pub fn expand_response_files(arguments: Vec<OsString>) -> Result<Vec<OsString>, String> {
+    let mut expanded = Vec::with_capacity(arguments.len());
+    expand_into(arguments, 0, &mut expanded)?;
+    Ok(expanded)
+}
+
+/// Replaces a `@path` argument at the front, where a command module expects a
+/// command name, leaving the rest for the command to expand.
+pub fn expand_leading_response_file(arguments: &mut Vec<OsString>) -> Result<(), String> {
+    let mut depth = 0;
+    while let Some(path) = arguments.first().and_then(|first| response_file(first)) {
+        if depth == RESPONSE_FILE_DEPTH {
+            return Err(too_deep(&path));
+        }
+        let contents = read_response_file(&path)?;
+        arguments.splice(..1, contents);
+        depth += 1;
+    }
+    Ok(())
+}
+
+fn expand_into(
+    arguments: Vec<OsString>,
+    depth: usize,
+    expanded: &mut Vec<OsString>,
+) -> Result<(), String> {
+    for argument in arguments {
+        match response_file(&argument) {
+            Some(path) if depth == RESPONSE_FILE_DEPTH => return Err(too_deep(&path)),
+            Some(path) => expand_into(read_response_file(&path)?, depth + 1, expanded)?,
+            None => expanded.push(argument),
+        }
+    }
+    Ok(())
+}
+
+/// The path named by an `@path` argument.
+fn response_file(argument: &OsStr) -> Option<std::path::PathBuf> {
+    let bytes = argument.as_encoded_bytes();
+    if bytes.len() < 2 || bytes[0] != b'@' {
+        return None;
+    }
+    // SAFETY: the split follows an ASCII `@`, which is a valid split point of
+    // the platform encoding.
+    let path = unsafe { OsStr::from_encoded_bytes_unchecked(&bytes[1..]) };
+    Some(path.into())
+}
+
+fn too_deep(path: &std::path::Path) -> String {
+    format!(
+        "response file '{}' is nested more than {} levels deep",
+        path.display(),
+        RESPONSE_FILE_DEPTH
+    )
+}
+
+fn read_response_file(path: &std::path::Path) -> Result<Vec<OsString>, String> {
+    let contents = std::fs::read(path)
+        .map_err(|error| format!("cannot read response file '{}': {}", path.display(), error))?;
+    split_arguments(&contents)
+        .map_err(|problem| format!("invalid response file '{}': {}", path.display(), problem))
+}
+
+/// Splits the contents of a response file into arguments, separated by
+/// whitespace and quoted like in a shell: `'...'` is taken literally, `"..."`
+/// allows `\"` and `\\`, and a backslash outside quotes escapes the next
+/// character. A `#` that does not continue an argument starts a comment that
+/// runs to the end of the line. The contents are split as bytes, so arguments
+/// need not be valid UTF-8 where the platform allows that.
+pub(crate) fn split_arguments(text: &[u8]) -> Result<Vec<OsString>, &'static str> {
+    let mut arguments = Vec::new();
+    let mut current = Vec::new();
+    let mut in_argument = false;
+    let mut bytes = text.iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            byte if byte.is_ascii_whitespace() => {
+                if in_argument {
+                    arguments.push(os_string(std::mem::take(&mut current))?);
+                    in_argument = false;
+                }
+            }
+            b'#' if !in_argument => {
+                for byte in bytes.by_ref() {
+                    if byte == b'\n' {
+                        break;
+                    }
+                }
+            }
+            b'\'' => {
+                in_argument = true;
+                loop {
+                    match bytes.next() {
+                        Some(b'\'') => break,
+                        Some(byte) => current.push(byte),
+                        None => return Err("unterminated single quote"),
+                    }
+                }
+            }
+            b'"' => {
+                in_argument = true;
+                loop {
+                    match bytes.next() {
+                        Some(b'"') => break,
+                        Some(b'\\') if matches!(bytes.peek(), Some(b'"' | b'\\')) => {
+                            current.extend(bytes.next());
+                        }
+                        Some(byte) => current.push(byte),
+                        None => return Err("unterminated double quote"),
+                    }
+                }
+            }
+            b'\\' => match bytes.next() {
+                Some(b'\n') => {}
+                Some(byte) => {
+                    in_argument = true;
+                    current.push(byte);
+                }
+                None => return Err("trailing backslash"),
+            },
+            byte => {
+                in_argument = true;
+                current.push(byte);
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(os_string(current)?);
+    }
+    Ok(arguments)
+}
+
+/// Converts the bytes of an argument read from a file. Unix takes any bytes;
+/// elsewhere they must be UTF-8.
+#[cfg(unix)]
+fn os_string(bytes: Vec<u8>) -> Result<OsString, &'static str> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Ok(OsString::from_vec(bytes))
+}
+
+#[cfg(not(unix))]
+fn os_string(bytes: Vec<u8>) -> Result<OsString, &'static str> {
+    String::from_utf8(bytes)
+        .map(OsString::from)
+        .map_err(|_| "argument is not valid UTF-8")
+}
+
+/// Whether the help and errors that runners return are printed by the
+/// generated `main`, rather than returned to a caller such as a test.
+static PRINTED: AtomicBool = AtomicBool::new(false);
+
+/// Called by the generated `main` before running the command, so that help may
+/// be styled for the terminal it is printed to.
+pub fn print_results() {
+    PRINTED.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`print_results`] was called.
+pub fn results_printed() -> bool {
+    PRINTED.load(Ordering::Relaxed)
+}
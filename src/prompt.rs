@@ -0,0 +1,236 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::marker::PhantomData;
+
+/// A type with a fixed set of values, such as an enum, offered as a menu when
+/// a missing option is asked for interactively.
+///
+/// The names are passed to [`FromStr`](std::str::FromStr) when chosen, so they
+/// must be spellings the type parses. There is no derive for either trait, so
+/// the names are written twice, once here and once in `from_str`; a name
+/// missing from `from_str` is reported like any other answer that does not
+/// parse, and asked for again.
+///
+/// ```no_run
+/// enum Cluster {
+///     Eu,
+///     Us,
+/// }
+///
+/// impl std::str::FromStr for Cluster {
+///     type Err = String;
+///
+///     fn from_str(value: &str) -> Result<Self, Self::Err> {
+///         match value {
+///             "eu" => Ok(Cluster::Eu),
+///             "us" => Ok(Cluster::Us),
+///             _ => Err(format!("unknown cluster {value}")),
+///         }
+///     }
+/// }
+///
+/// impl fire::Choices for Cluster {
+///     fn choices() -> &'static [&'static str] {
+///         &["eu", "us"]
+///     }
+/// }
+///
+/// #[fire::main(prompt)]
+/// fn deploy(
+///     /// Cluster to deploy to.
+///     cluster: Cluster,
+/// ) {}
+/// ```
+pub trait Choices {
+    /// Returns the names of every value, in the order they are offered.
+    fn choices() -> &'static [&'static str];
+}
+
+/// The choices of `T` when it implements [`Choices`]. Method resolution
+/// prefers the implementation on `Offer` over the fallback on `&Offer`.
+pub struct Offer<T: ?Sized>(pub PhantomData<T>);
+
+pub trait ViaChoices {
+    fn choices(&self) -> Option<&'static [&'static str]>;
+}
+
+impl<T: Choices + ?Sized> ViaChoices for Offer<T> {
+    fn choices(&self) -> Option<&'static [&'static str]> {
+        Some(T::choices())
+    }
+}
+
+pub trait WithoutChoices {
+    fn choices(&self) -> Option<&'static [&'static str]>;
+}
+
+impl<T: ?Sized> WithoutChoices for &Offer<T> {
+    fn choices(&self) -> Option<&'static [&'static str]> {
+        None
+    }
+}
+
+/// Whether a person can be asked for missing values: standard input and the
+/// standard error the question goes to must both be terminals.
+pub fn interactive() -> bool {
+    io::stdin().is_terminal() && io::stderr().is_terminal()
+}
+
+/// Asks `question` on standard error until an answer is given, and returns
+/// `None` at the end of input. With `secret`, the answer is typed without
+/// echo. See [`ask`] for the rest.
+pub fn prompt(
+    question: &str,
+    choices: Option<&[&str]>,
+    secret: bool,
+    check: impl FnMut(&str) -> Result<(), String>,
+) -> Option<String> {
+    let hidden = secret && set_echo(false);
+    let answer = ask(
+        &mut io::stdin().lock(),
+        &mut io::stderr(),
+        question,
+        choices,
+        secret,
+        check,
+    );
+    if hidden {
+        set_echo(true);
+    }
+    answer
+}
+
+/// Writes `question` to `output` and reads answers from `input` until one
+/// passes `check`, whose problems are shown before asking again. Returns
+/// `None` at the end of input. With `choices`, they are listed as a numbered
+/// menu and either a number or a name is accepted. With `secret`, the answer
+/// is taken exactly as typed, and since it was not echoed, the newline after
+/// it is written.
+pub fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+    choices: Option<&[&str]>,
+    secret: bool,
+    mut check: impl FnMut(&str) -> Result<(), String>,
+) -> Option<String> {
+    let question = question.trim_end_matches(['.', ':', '?']);
+    loop {
+        // The question is best effort; answers are still read if it cannot be
+        // shown.
+        let _ = match choices {
+            Some(choices) => {
+                let mut menu = format!("{question}:\n");
+                for (index, choice) in choices.iter().enumerate() {
+                    menu.push_str(&format!("  {}) {}\n", index + 1, choice));
+                }
+                write!(output, "{menu}Choose 1-{}: ", choices.len())
+            }
+            None => write!(output, "{question}: "),
+        };
+        let _ = output.flush();
+        let mut line = String::new();
+        let read = input.read_line(&mut line);
+        if secret {
+            let _ = writeln!(output);
+        }
+        if read.ok()? == 0 {
+            if !secret {
+                let _ = writeln!(output);
+            }
+            return None;
+        }
+        let answer = if secret {
+            line.trim_end_matches(['\r', '\n'])
+        } else {
+            line.trim()
+        };
+        if answer.is_empty() {
+            continue;
+        }
+        let answer = match choices {
+            None => answer,
+            Some(choices) => {
+                let chosen = match answer.parse::<usize>() {
+                    Ok(number) => number.checked_sub(1).and_then(|index| choices.get(index)),
+                    Err(_) => choices.iter().find(|choice| **choice == answer),
+                };
+                match chosen {
+                    Some(choice) => choice,
+                    None => {
+                        let _ = writeln!(output, "'{answer}' is not one of the choices");
+                        continue;
+                    }
+                }
+            }
+        };
+        match check(answer) {
+            Ok(()) => return Some(answer.to_string()),
+            Err(problem) => {
+                let _ = writeln!(output, "{problem}");
+            }
+        }
+    }
+}
+
+/// Asks a yes-or-no `question` on standard error. See [`confirm_with`].
+pub fn confirm(question: &str) -> bool {
+    confirm_with(&mut io::stdin().lock(), &mut io::stderr(), question)
+}
+
+/// Writes a yes-or-no `question` to `output` and reads the answer from
+/// `input`. Only `y` or `yes` agree; anything else, including the end of
+/// input, declines.
+pub fn confirm_with(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> bool {
+    let _ = write!(output, "{} [y/N] ", question.trim_end());
+    let _ = output.flush();
+    let mut line = String::new();
+    if input.read_line(&mut line).unwrap_or(0) == 0 {
+        let _ = writeln!(output);
+        return false;
+    }
+    matches!(line.trim().to_ascii_lowercase().as_str(), "y" | "yes")
+}
+
+/// Turns echo of the terminal on standard input on or off, and returns
+/// whether that worked.
+#[cfg(unix)]
+fn set_echo(enabled: bool) -> bool {
+    std::process::Command::new("stty")
+        .arg(if enabled { "echo" } else { "-echo" })
+        .stdin(std::process::Stdio::inherit())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+#[cfg(windows)]
+fn set_echo(enabled: bool) -> bool {
+    use std::ffi::c_void;
+
+    const STD_INPUT_HANDLE: u32 = -10i32 as u32;
+    const ENABLE_ECHO_INPUT: u32 = 0x0004;
+    extern "system" {
+        fn GetStdHandle(handle: u32) -> *mut c_void;
+        fn GetConsoleMode(console: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(console: *mut c_void, mode: u32) -> i32;
+    }
+    // SAFETY: the handle comes from `GetStdHandle` and `mode` outlives the
+    // calls that use it.
+    unsafe {
+        let console = GetStdHandle(STD_INPUT_HANDLE);
+        let mut mode = 0;
+        if GetConsoleMode(console, &mut mode) == 0 {
+            return false;
+        }
+        let mode = if enabled {
+            mode | ENABLE_ECHO_INPUT
+        } else {
+            mode & !ENABLE_ECHO_INPUT
+        };
+        SetConsoleMode(console, mode) != 0
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+fn set_echo(_enabled: bool) -> bool {
+    false
+}
@@ -0,0 +1,208 @@
+//! The interactive shell of `#[fire::main(repl)]` and the `--fire-batch` mode
+//! of command modules. Not public API.
+
+use std::ffi::{OsStr, OsString};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+
+use crate::private::split_arguments;
+
+/// Reads command lines from standard input until `exit` or the end of input,
+/// and runs each one with `run`, the dispatcher of the command module. See
+/// [`run_shell`] for the rest.
+pub fn shell(
+    program: &str,
+    run: impl FnMut(Vec<OsString>) -> Result<Option<String>, String>,
+) -> Result<Option<String>, String> {
+    // Without a terminal, the lines come from a script and the prompt would
+    // only clutter the output.
+    let interactive = io::stdin().is_terminal();
+    run_shell(
+        &mut io::stdin().lock(),
+        &mut io::stdout(),
+        &mut io::stderr(),
+        interactive,
+        program,
+        run,
+    )
+}
+
+/// Reads command lines from `input` until `exit`, `quit`, or the end of
+/// input, and runs each one with `run`. Lines are split like response files.
+/// Output goes to `output` and errors to `errors`, after which the session
+/// goes on. When `interactive`, each line is prompted for with `program`.
+pub fn run_shell(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+    interactive: bool,
+    program: &str,
+    mut run: impl FnMut(Vec<OsString>) -> Result<Option<String>, String>,
+) -> Result<Option<String>, String> {
+    let mut history: Vec<String> = Vec::new();
+    // The session is best effort about its output, as `print!` would be,
+    // but without panicking on a closed pipe.
+    loop {
+        if interactive {
+            let _ = write!(output, "{program}> ");
+            let _ = output.flush();
+        }
+        let mut line = String::new();
+        match input.read_line(&mut line) {
+            Ok(0) => {
+                if interactive {
+                    let _ = writeln!(output);
+                }
+                return Ok(None);
+            }
+            Ok(_) => {}
+            Err(error) => return Err(format!("cannot read the next command: {error}")),
+        }
+        let arguments = match split_arguments(line.as_bytes()) {
+            Ok(arguments) => arguments,
+            Err(problem) => {
+                let _ = writeln!(errors, "error: {problem}");
+                continue;
+            }
+        };
+        let Some(first) = arguments.first() else {
+            continue;
+        };
+        history.push(line.trim().to_string());
+        let arguments = match first.to_str().unwrap_or_default() {
+            "exit" | "quit" => return Ok(None),
+            "history" => {
+                for (index, entry) in history.iter().enumerate() {
+                    let _ = writeln!(output, "{:>5}  {}", index + 1, entry);
+                }
+                continue;
+            }
+            "shell" => {
+                let _ = writeln!(errors, "error: the shell is already running");
+                continue;
+            }
+            _ if starts_batch(&arguments) => {
+                let _ = writeln!(errors, "error: a batch cannot run inside the shell");
+                continue;
+            }
+            "help" => match arguments.get(1) {
+                Some(command) => vec![command.clone(), "--help".into()],
+                None => {
+                    if let Ok(Some(help)) = run(vec!["--help".into()]) {
+                        let _ = writeln!(output, "{help}\n");
+                    }
+                    let _ = writeln!(output, "In the shell, also: help [COMMAND], history, exit");
+                    continue;
+                }
+            },
+            _ => arguments,
+        };
+        match run(arguments) {
+            Ok(Some(text)) => {
+                let _ = writeln!(output, "{text}");
+            }
+            Ok(None) => {}
+            Err(error) => {
+                let _ = writeln!(errors, "error: {error}");
+            }
+        }
+    }
+}
+
+/// Runs the command lines of the file at `path`, or of standard input for
+/// `-`, with `run`. See [`run_batch`] for the rest.
+pub fn batch(
+    path: &OsStr,
+    run: impl FnMut(Vec<OsString>) -> Result<Option<String>, String>,
+) -> Result<Option<String>, String> {
+    let (mut output, mut errors) = (io::stdout(), io::stderr());
+    if path == "-" {
+        return run_batch(&mut io::stdin().lock(), &mut output, &mut errors, run);
+    }
+    let file = File::open(path).map_err(|error| {
+        format!(
+            "cannot read batch file '{}': {}",
+            path.to_string_lossy(),
+            error
+        )
+    })?;
+    run_batch(&mut BufReader::new(file), &mut output, &mut errors, run)
+}
+
+/// Runs the command lines of `input`, one per line, with `run`, writing their
+/// output to `output` and their failures to `errors`. `#` starts a comment.
+/// After `set -e`, the first failing line stops the batch; otherwise failures
+/// are written and the remaining lines still run, and `set +e` returns to
+/// that. Each failure names its line, and the batch fails if any line did.
+/// Lines are read as bytes, so arguments need not be UTF-8 on Unix.
+pub fn run_batch(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+    mut run: impl FnMut(Vec<OsString>) -> Result<Option<String>, String>,
+) -> Result<Option<String>, String> {
+    let mut stop_on_error = false;
+    let mut commands = 0;
+    let mut failures = 0;
+    let mut line = Vec::new();
+    for number in 1.. {
+        line.clear();
+        match input.read_until(b'\n', &mut line) {
+            Ok(0) => break,
+            Ok(_) => {}
+            Err(error) => return Err(format!("cannot read line {number}: {error}")),
+        }
+        // Files written on Windows end their lines with "\r\n".
+        let text = line.strip_suffix(b"\n").unwrap_or(&line);
+        let text = text.strip_suffix(b"\r").unwrap_or(text);
+        let arguments = match split_arguments(text) {
+            Ok(arguments) if arguments.is_empty() => continue,
+            Ok(arguments) => arguments,
+            Err(problem) => return Err(format!("line {number}: {problem}")),
+        };
+        match arguments
+            .iter()
+            .map(|argument| argument.to_str().unwrap_or_default())
+            .collect::<Vec<_>>()
+            .as_slice()
+        {
+            ["set", "-e"] => {
+                stop_on_error = true;
+                continue;
+            }
+            ["set", "+e"] => {
+                stop_on_error = false;
+                continue;
+            }
+            _ if starts_batch(&arguments) => {
+                return Err(format!("line {number}: a batch cannot start another batch"));
+            }
+            _ => {}
+        }
+        commands += 1;
+        match run(arguments) {
+            Ok(Some(text)) => {
+                let _ = writeln!(output, "{text}");
+            }
+            Ok(None) => {}
+            Err(error) if stop_on_error => return Err(format!("line {number}: {error}")),
+            Err(error) => {
+                let _ = writeln!(errors, "error: line {number}: {error}");
+                failures += 1;
+            }
+        }
+    }
+    if failures == 0 {
+        Ok(None)
+    } else {
+        Err(format!("{failures} of {commands} commands failed"))
+    }
+}
+
+/// Whether a command line given to the shell or a batch would start a batch of
+/// its own, also after built-in options such as `--color`.
+fn starts_batch(arguments: &[OsString]) -> bool {
+    arguments.iter().any(|argument| {
+        argument == "--fire-batch" || argument.as_encoded_bytes().starts_with(b"--fire-batch=")
+    })
+}
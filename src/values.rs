@@ -0,0 +1,277 @@
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// A span of time written the way people type it, such as `30s`, `5m`,
+/// `1h30m`, or `1.5d`.
+///
+/// Each component is a number followed by a unit: `ns`, `us` (or `µs`),
+/// `ms`, `s`, `m`, `h`, or `d`. A plain `0` is also accepted. The value
+/// dereferences to [`std::time::Duration`] and converts into it.
+///
+/// ```
+/// let timeout: fire::Duration = "1m30s".parse().unwrap();
+/// assert_eq!(timeout.as_secs(), 90);
+/// assert_eq!(timeout.to_string(), "1m30s");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub std::time::Duration);
+
+const DURATION_UNITS: [(&str, u128); 8] = [
+    ("ns", 1),
+    ("us", 1_000),
+    ("µs", 1_000),
+    ("ms", 1_000_000),
+    ("s", 1_000_000_000),
+    ("m", 60 * 1_000_000_000),
+    ("h", 60 * 60 * 1_000_000_000),
+    ("d", 24 * 60 * 60 * 1_000_000_000),
+];
+
+impl FromStr for Duration {
+    type Err = ValueError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        if value == "0" {
+            return Ok(Duration(std::time::Duration::ZERO));
+        }
+        if value.is_empty() {
+            return Err(ValueError("empty duration".to_string()));
+        }
+        let mut nanoseconds = 0u128;
+        let mut rest = value;
+        while !rest.is_empty() {
+            let number_length = rest
+                .find(|character: char| !character.is_ascii_digit() && character != '.')
+                .ok_or_else(|| ValueError(format!("missing unit after '{rest}'")))?;
+            let (number, after) = rest.split_at(number_length);
+            let unit_length = after
+                .find(|character: char| character.is_ascii_digit() || character == '.')
+                .unwrap_or(after.len());
+            let (unit, after) = after.split_at(unit_length);
+            let scale = DURATION_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, scale)| *scale)
+                .ok_or_else(|| {
+                    ValueError(format!(
+                        "unknown unit '{unit}'; expected ns, us, ms, s, m, h, or d"
+                    ))
+                })?;
+            nanoseconds = scaled(number, scale)
+                .and_then(|component| nanoseconds.checked_add(component))
+                .ok_or_else(|| ValueError(format!("invalid duration '{value}'")))?;
+            rest = after;
+        }
+        let seconds = u64::try_from(nanoseconds / 1_000_000_000)
+            .map_err(|_| ValueError("duration is too long".to_string()))?;
+        let nanoseconds = (nanoseconds % 1_000_000_000) as u32;
+        Ok(Duration(std::time::Duration::new(seconds, nanoseconds)))
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut rest = self.0.as_nanos();
+        if rest == 0 {
+            return formatter.write_str("0s");
+        }
+        for (unit, scale) in DURATION_UNITS.iter().rev() {
+            if *unit == "µs" {
+                continue;
+            }
+            if rest >= *scale {
+                write!(formatter, "{}{unit}", rest / scale)?;
+                rest %= scale;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Deref for Duration {
+    type Target = std::time::Duration;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Duration> for std::time::Duration {
+    fn from(duration: Duration) -> Self {
+        duration.0
+    }
+}
+
+impl From<std::time::Duration> for Duration {
+    fn from(duration: std::time::Duration) -> Self {
+        Duration(duration)
+    }
+}
+
+/// A number of bytes written with an optional unit, such as `512`, `64KiB`,
+/// `512MiB`, or `1.5GB`.
+///
+/// Units are case-insensitive. `kB`, `MB`, `GB`, `TB`, and `PB` are powers of
+/// 1000; `KiB`, `MiB`, `GiB`, `TiB`, and `PiB`, as well as the shorthands `K`,
+/// `M`, `G`, `T`, and `P`, are powers of 1024. The value dereferences to the
+/// number of bytes as a `u64`.
+///
+/// ```
+/// let size: fire::ByteSize = "512MiB".parse().unwrap();
+/// assert_eq!(*size, 512 * 1024 * 1024);
+/// assert_eq!(size.to_string(), "512MiB");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+const BYTE_UNITS: [(&str, u128); 16] = [
+    ("b", 1),
+    ("kb", 1_000),
+    ("mb", 1_000_000),
+    ("gb", 1_000_000_000),
+    ("tb", 1_000_000_000_000),
+    ("pb", 1_000_000_000_000_000),
+    ("k", 1 << 10),
+    ("m", 1 << 20),
+    ("g", 1 << 30),
+    ("t", 1 << 40),
+    ("p", 1 << 50),
+    ("kib", 1 << 10),
+    ("mib", 1 << 20),
+    ("gib", 1 << 30),
+    ("tib", 1 << 40),
+    ("pib", 1 << 50),
+];
+
+impl FromStr for ByteSize {
+    type Err = ValueError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let number_length = value
+            .find(|character: char| !character.is_ascii_digit() && character != '.')
+            .unwrap_or(value.len());
+        let (number, unit) = value.split_at(number_length);
+        let unit = unit.trim_start().to_ascii_lowercase();
+        let scale = if unit.is_empty() {
+            1
+        } else {
+            BYTE_UNITS
+                .iter()
+                .find(|(name, _)| *name == unit)
+                .map(|(_, scale)| *scale)
+                .ok_or_else(|| {
+                    ValueError(format!(
+                        "unknown unit '{}'; expected B, kB, KiB, MB, MiB, GB, GiB, TB, TiB, PB, \
+                         or PiB",
+                        &value[number_length..]
+                    ))
+                })?
+        };
+        scaled(number, scale)
+            .and_then(|bytes| u64::try_from(bytes).ok())
+            .map(ByteSize)
+            .ok_or_else(|| ValueError(format!("invalid size '{value}'")))
+    }
+}
+
+impl fmt::Display for ByteSize {
+    // `u64::is_multiple_of` needs Rust 1.87.
+    #[allow(clippy::manual_is_multiple_of)]
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: [&str; 5] = ["PiB", "TiB", "GiB", "MiB", "KiB"];
+        for (index, unit) in UNITS.iter().enumerate() {
+            let scale = 1u64 << (10 * (UNITS.len() - index));
+            if self.0 != 0 && self.0 % scale == 0 {
+                return write!(formatter, "{}{unit}", self.0 / scale);
+            }
+        }
+        write!(formatter, "{}B", self.0)
+    }
+}
+
+impl Deref for ByteSize {
+    type Target = u64;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+/// The number of times a flag was given, as in `-vvv`.
+///
+/// A `Count` parameter is a flag that may be repeated. Besides its long name,
+/// it has a short name made of its first letter, and short names can be
+/// combined, so `-v -v -v`, `-vvv`, and `--verbose -vv` all count 3. The count
+/// stops at 255.
+///
+/// ```no_run
+/// #[fire::main]
+/// fn build(verbose: fire::Count) {
+///     if *verbose >= 2 {
+///         println!("tracing enabled");
+///     }
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Count(pub u8);
+
+impl Deref for Count {
+    type Target = u8;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<Count> for u8 {
+    fn from(count: Count) -> Self {
+        count.0
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(formatter)
+    }
+}
+
+/// Multiplies a decimal number such as `1.5` by `scale` without going through
+/// floating point, rounding down to a whole number.
+fn scaled(number: &str, scale: u128) -> Option<u128> {
+    let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+    if whole.is_empty() && fraction.is_empty() {
+        return None;
+    }
+    let digits = |text: &str| -> Option<u128> {
+        if text.is_empty() {
+            Some(0)
+        } else if text.bytes().all(|byte| byte.is_ascii_digit()) {
+            text.parse().ok()
+        } else {
+            None
+        }
+    };
+    let whole = digits(whole)?.checked_mul(scale)?;
+    let fraction_scale = 10u128.checked_pow(fraction.len() as u32)?;
+    let fraction = digits(fraction)?.checked_mul(scale)? / fraction_scale;
+    whole.checked_add(fraction)
+}
+
+/// The error returned when parsing [`Duration`] or [`ByteSize`] fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueError(String);
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ValueError {}
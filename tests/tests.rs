@@ -24,7 +24,7 @@ mod single_command {
     pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
     where
         I: IntoIterator<Item = S>,
-        S: Into<String>,
+        S: Into<std::ffi::OsString>,
     {
         __fire_run_hello(args)
     }
@@ -52,7 +52,7 @@ mod command_group {
     pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
     where
         I: IntoIterator<Item = S>,
-        S: Into<String>,
+        S: Into<std::ffi::OsString>,
     {
         cli::__fire_run(args)
     }
@@ -73,7 +73,7 @@ mod async_single_command {
     pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
     where
         I: IntoIterator<Item = S>,
-        S: Into<String>,
+        S: Into<std::ffi::OsString>,
     {
         __fire_run_hello(args)
     }
@@ -103,7 +103,734 @@ mod async_command_group {
     pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
     where
         I: IntoIterator<Item = S>,
-        S: Into<String>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+#[allow(dead_code)]
+mod documented_command_group {
+    /// Release management.
+    ///
+    /// Commands for building and publishing releases.
+    #[fire::main]
+    mod cli {
+        /// Publish a release.
+        ///
+        /// Uploads every artifact and then tags the release.
+        ///
+        /// Tags are never moved once pushed.
+        pub fn publish(
+            /// Release version.
+            ///
+            /// Must follow semantic versioning.
+            version: String,
+        ) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("publish:{version}"));
+        }
+
+        /// Roll back to a **previous** release.
+        ///
+        /// Restores the artifacts of `version`. Read the
+        /// [runbook](https://example.com/runbook) first:
+        ///
+        /// - notify the *on-call* engineer
+        /// - freeze [`publish`]
+        ///
+        /// # Examples
+        ///
+        /// ```console
+        /// $ app rollback --version 1.2.0
+        /// ```
+        pub fn rollback(
+            /// Release to restore, e.g. `1.2.0`.
+            version: String,
+        ) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("rollback:{version}"));
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+mod literal_markup_command {
+    /// Call a Python hook such as `setup` or __init__ with **care**.
+    ///
+    /// Names match [a-z] or [0-9], as in [the guide][guide], and the limit
+    /// is 2*3*4 [default: 1]; snake_case and __very important__ stay readable.
+    #[fire::main]
+    fn hook(
+        /// Pattern like [0-9]+ [default: .*].
+        pattern: String,
+    ) {
+        super::CALLS.lock().unwrap().push(format!("hook:{pattern}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_hook(args)
+    }
+}
+
+#[allow(dead_code)]
+mod abbreviated_command_group {
+    #[fire::main(abbrev)]
+    mod cli {
+        pub fn deploy(verbose: bool, version: Option<String>, target: String) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("deploy:{target}:{version:?}:{verbose}"));
+        }
+
+        pub fn describe() {}
+
+        pub fn status() {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push("status".to_string());
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+#[allow(dead_code)]
+mod overriding_command {
+    #[fire::main]
+    fn configure(#[fire(overrides)] level: u8, #[fire(overrides)] force: bool) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("configure:{level}:{force}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_configure(args)
+    }
+}
+
+#[allow(dead_code)]
+mod typed_command {
+    pub struct Level;
+
+    impl std::str::FromStr for Level {
+        type Err = ();
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "high" => Ok(Level),
+                _ => Err(()),
+            }
+        }
+    }
+
+    #[fire::main]
+    fn listen(port: u16, level: Option<Level>) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("listen:{port}:{}", level.is_some()));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_listen(args)
+    }
+}
+
+#[allow(dead_code)]
+mod custom_parsing_command {
+    use std::time::Duration;
+
+    fn parse_seconds(value: &str) -> Result<Duration, String> {
+        let seconds = value
+            .strip_suffix('s')
+            .ok_or_else(|| "missing 's' suffix".to_string())?;
+        seconds
+            .parse()
+            .map(Duration::from_secs)
+            .map_err(|_| format!("'{seconds}' is not a number of seconds"))
+    }
+
+    fn is_unprivileged(port: &u16) -> bool {
+        *port >= 1024
+    }
+
+    fn is_short(timeout: &Duration) -> Result<(), String> {
+        if timeout.as_secs() <= 60 {
+            Ok(())
+        } else {
+            Err("timeout must be at most a minute".to_string())
+        }
+    }
+
+    #[fire::main]
+    fn serve(
+        #[fire(validate = is_unprivileged)] port: u16,
+        #[fire(parse_with = parse_seconds, validate = is_short)] timeout: Option<Duration>,
+    ) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("serve:{port}:{timeout:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_serve(args)
+    }
+}
+
+#[allow(dead_code)]
+mod human_values_command {
+    #[fire::main]
+    fn limit(
+        /// Request timeout.
+        timeout: fire::Duration,
+        max_size: Option<fire::ByteSize>,
+        mask: u8,
+        offset: i64,
+    ) {
+        super::CALLS.lock().unwrap().push(format!(
+            "limit:{}:{:?}:{mask}:{offset}",
+            timeout.as_millis(),
+            max_size.map(|size| size.0)
+        ));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_limit(args)
+    }
+}
+
+mod file_command {
+    use std::io::{BufRead, Write};
+
+    /// Upper-case text.
+    #[fire::main]
+    fn shout(input: fire::Input, mut output: fire::Output) -> std::io::Result<()> {
+        for line in input.lines() {
+            writeln!(output, "{}", line?.to_uppercase())?;
+        }
+        output.flush()
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_shout(args)
+    }
+}
+
+mod same_named_command {
+    use std::str::FromStr;
+
+    pub struct Input(String);
+
+    impl FromStr for Input {
+        type Err = std::convert::Infallible;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            Ok(Input(value.to_string()))
+        }
+    }
+
+    pub struct Count(u8);
+
+    impl FromStr for Count {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            value.parse().map(Count)
+        }
+    }
+
+    pub struct Duration(u64);
+
+    impl FromStr for Duration {
+        type Err = std::num::ParseIntError;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            value.parse().map(Duration)
+        }
+    }
+
+    #[fire::main]
+    fn tally(input: Input, count: Count, timeout: Duration) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("tally:{}:{}:{}", input.0, count.0, timeout.0));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_tally(args)
+    }
+}
+
+mod path_command {
+    use std::ffi::OsString;
+    use std::path::PathBuf;
+
+    #[fire::main]
+    fn open(path: PathBuf, name: Option<OsString>, label: Option<String>) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("open:{path:?}:{name:?}:{label:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_open(args)
+    }
+}
+
+mod borrowed_command {
+    use std::ffi::OsStr;
+    use std::path::Path;
+
+    #[fire::main]
+    fn archive(
+        root: &Path,
+        label: Option<&OsStr>,
+        /// Paths to include.
+        include: &[String],
+        exclude: &[&str],
+        level: Vec<u8>,
+    ) {
+        super::CALLS.lock().unwrap().push(format!(
+            "archive:{}:{label:?}:{include:?}:{exclude:?}:{level:?}",
+            root.display()
+        ));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_archive(args)
+    }
+}
+
+mod negatable_command {
+    #[fire::main]
+    fn build(
+        /// Reuse earlier results.
+        #[fire(default = true)]
+        cache: bool,
+        release: bool,
+        /// Colorize compiler output.
+        color: Option<bool>,
+    ) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("build:{cache}:{release}:{color:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_build(args)
+    }
+}
+
+mod counting_command {
+    #[fire::main]
+    fn log(
+        /// More output; repeat for even more.
+        verbose: fire::Count,
+        #[fire(count)] quiet: i8,
+        name: Option<String>,
+    ) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("log:{verbose}:{quiet}:{name:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_log(args)
+    }
+}
+
+mod related_command {
+    use std::path::PathBuf;
+
+    #[fire::main]
+    #[allow(clippy::too_many_arguments)]
+    fn export(
+        #[fire(group(format, required))] json: bool,
+        #[fire(group = format)] csv: bool,
+        #[fire(requires = csv)] delimiter: Option<char>,
+        output: Option<PathBuf>,
+        #[fire(conflicts_with = output)] stdout: bool,
+        #[fire(group(filter, multiple))] include: Vec<String>,
+        #[fire(group = filter)] exclude: Vec<String>,
+        #[fire(requires = csv)] header: bool,
+    ) {
+        super::CALLS.lock().unwrap().push(format!(
+            "export:{json}:{csv}:{delimiter:?}:{output:?}:{stdout}:{include:?}:{exclude:?}:{header}"
+        ));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_export(args)
+    }
+}
+
+mod structured_command {
+    use std::collections::{BTreeMap, HashMap};
+
+    #[fire::main]
+    fn plot(
+        point: (i32, i32),
+        /// Labels to attach.
+        label: BTreeMap<String, String>,
+        limit: HashMap<String, u8>,
+        range: Option<(u8, u8, String)>,
+    ) {
+        let mut limits: Vec<_> = limit.into_iter().collect();
+        limits.sort();
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("plot:{point:?}:{label:?}:{limits:?}:{range:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_plot(args)
+    }
+}
+
+mod optional_value_command {
+    use std::path::PathBuf;
+
+    #[fire::main]
+    fn serve(
+        /// Write a log, to the given file or the default one.
+        log: Option<Option<PathBuf>>,
+        level: Option<Option<u8>>,
+        name: Option<String>,
+    ) {
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("serve:{log:?}:{level:?}:{name:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_serve(args)
+    }
+}
+
+mod response_file_command_group {
+    #[fire::main]
+    mod cli {
+        pub fn tag(ids: Vec<u32>, name: Option<String>) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("tag:{ids:?}:{name:?}"));
+        }
+
+        #[fire(no_response_files)]
+        pub fn mention(handle: String) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("mention:{handle}"));
+        }
+
+        pub fn archive(path: std::path::PathBuf) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("archive:{path:?}"));
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+mod configured_command_group {
+    #[fire::main(config = "fire-tests-missing.toml")]
+    mod cli {
+        pub fn deploy(
+            #[fire(env = "FIRE_TESTS_DEPLOY_TARGET")] target: String,
+            port: Option<u16>,
+            tags: Vec<String>,
+            force: bool,
+            #[fire(secret)] token: Option<String>,
+        ) {
+            let _ = token;
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("deploy:{target}:{port:?}:{tags:?}:{force}"));
+        }
+
+        pub fn export(
+            #[fire(group = format)] json: bool,
+            #[fire(group = format)] csv: bool,
+            #[fire(conflicts_with = stdout)] output: Option<String>,
+            stdout: bool,
+            #[fire(requires = csv)] delimiter: Option<char>,
+        ) {
+            super::super::CALLS.lock().unwrap().push(format!(
+                "export:{json}:{csv}:{output:?}:{stdout}:{delimiter:?}"
+            ));
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+mod prompting_command {
+    pub enum Cluster {
+        Eu,
+        Us,
+    }
+
+    impl std::str::FromStr for Cluster {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value {
+                "eu" => Ok(Cluster::Eu),
+                "us" => Ok(Cluster::Us),
+                _ => Err(format!("unknown cluster {value}")),
+            }
+        }
+    }
+
+    impl fire::Choices for Cluster {
+        fn choices() -> &'static [&'static str] {
+            &["eu", "us"]
+        }
+    }
+
+    /// Roll out a release.
+    #[fire::main]
+    fn roll_out(
+        /// Cluster to deploy to.
+        #[fire(prompt)]
+        cluster: Cluster,
+        #[fire(prompt)] release: String,
+    ) {
+        let cluster = match cluster {
+            Cluster::Eu => "eu",
+            Cluster::Us => "us",
+        };
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("roll-out:{cluster}:{release}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_roll_out(args)
+    }
+}
+
+mod secret_command {
+    use std::collections::BTreeMap;
+
+    fn api_key(value: &str) -> Result<String, String> {
+        match value.strip_prefix("sk-") {
+            Some(_) => Ok(value.to_string()),
+            None => Err(format!("{value} does not start with sk-")),
+        }
+    }
+
+    pub struct Token(String);
+
+    impl std::str::FromStr for Token {
+        type Err = String;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            match value.strip_prefix("t-") {
+                Some(_) => Ok(Token(value.to_string())),
+                None => Err(format!("{value} does not start with t-")),
+            }
+        }
+    }
+
+    #[fire::main]
+    fn connect(
+        #[fire(secret, parse_with = api_key)] key: String,
+        #[fire(secret)] pin: Option<u16>,
+        #[fire(secret)] login: Option<(String, Token)>,
+        #[fire(secret)] header: BTreeMap<String, Token>,
+    ) {
+        let login = login.map(|(user, token)| format!("{user}:{}", token.0));
+        let headers: Vec<_> = header.into_iter().map(|(name, token)| (name, token.0)).collect();
+        super::CALLS
+            .lock()
+            .unwrap()
+            .push(format!("connect:{key}:{pin:?}:{login:?}:{headers:?}"));
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        __fire_run_connect(args)
+    }
+}
+
+mod confirmed_command_group {
+    #[fire::main]
+    mod cli {
+        #[fire(confirm = "This deletes all data. Continue?")]
+        pub fn drop_database(name: String) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("drop-database:{name}"));
+        }
+
+        #[fire(confirm = "This replaces the backup. Continue?")]
+        pub fn restore(from: super::Backup, keep: u8) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("restore:{}:{keep}", from.0));
+        }
+    }
+
+    /// Records that it was parsed, to show when values are converted.
+    pub(crate) struct Backup(String);
+
+    impl std::str::FromStr for Backup {
+        type Err = std::convert::Infallible;
+
+        fn from_str(value: &str) -> Result<Self, Self::Err> {
+            super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("parsed-backup:{value}"));
+            Ok(Backup(value.to_string()))
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
+    {
+        cli::__fire_run(args)
+    }
+}
+
+mod shell_command_group {
+    #[fire::main(repl)]
+    mod cli {
+        /// Show the state of the cluster.
+        pub fn status() {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push("status".to_string());
+        }
+
+        /// Change the number of replicas.
+        pub fn scale(replicas: u8) {
+            super::super::CALLS
+                .lock()
+                .unwrap()
+                .push(format!("scale:{replicas}"));
+        }
+    }
+
+    pub(crate) fn run<I, S>(args: I) -> Result<Option<String>, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<std::ffi::OsString>,
     {
         cli::__fire_run(args)
     }
@@ -119,80 +846,1266 @@ fn assert_called(expected: &str) {
 }
 
 #[test]
-fn function_becomes_cli() {
-    single_command::run(["--name", "John", "--age=22", "--verbose"]).unwrap();
-    assert_called("John:22:None:true");
+fn function_becomes_cli() {
+    single_command::run(["--name", "John", "--age=22", "--verbose"]).unwrap();
+    assert_called("John:22:None:true");
+}
+
+#[test]
+fn module_functions_become_kebab_case_subcommands() {
+    command_group::run(["say-hello", "--name", "John"]).unwrap();
+    assert_called("hello:John");
+
+    command_group::run(["bye"]).unwrap();
+    assert_called("bye");
+}
+
+#[test]
+fn async_function_becomes_cli() {
+    async_single_command::run(["--name", "John"]).unwrap();
+    assert_called("async-hello:John");
+}
+
+#[test]
+fn async_module_mixes_async_and_sync_commands() {
+    async_command_group::run(["ping", "--host", "localhost"]).unwrap();
+    assert_called("ping:localhost");
+
+    async_command_group::run(["version"]).unwrap();
+    assert_called("version");
+
+    let error = async_command_group::run(["ping", "--host", "unreachable"]).unwrap_err();
+    assert_eq!(error, "host is unreachable");
+}
+
+#[test]
+fn errors_are_descriptive() {
+    let argument_error = single_command::run(["--age", "22"]).unwrap_err();
+    assert!(argument_error.starts_with("missing required option '--name'"));
+    assert!(argument_error.contains("Usage:"));
+    assert!(argument_error.contains("For more information, try '--help'."));
+
+    let command_error = command_group::run(["missing"]).unwrap_err();
+    assert!(command_error.starts_with("unknown command 'missing'"));
+    assert!(command_error.contains("Usage:"));
+    assert!(command_error.contains("For more information, try '--help'."));
+}
+
+#[test]
+fn option_is_not_consumed_as_another_options_value() {
+    let error = single_command::run(["--name", "--verbose", "--age", "22"]).unwrap_err();
+    assert!(error.starts_with("option '--name' requires a value"));
+    assert!(error.contains("Usage:"));
+}
+
+#[test]
+fn function_help_uses_signature_and_documentation() {
+    let help = single_command::run(["--help"]).unwrap().unwrap();
+    assert!(help.contains("Greet a person."));
+    assert!(help.contains("Usage:"));
+    assert!(help.contains("--name <NAME>"));
+    assert!(help.contains("Person to greet."));
+    assert!(help.contains("[--nickname <NICKNAME>]"));
+    assert!(help.contains("[--verbose]"));
+    assert!(help.contains("-h, --help"));
+}
+
+#[test]
+fn command_group_has_root_and_command_help() {
+    let root = command_group::run(["-h"]).unwrap().unwrap();
+    assert!(root.contains("Greeting commands."));
+    assert!(root.contains("say-hello"));
+    assert!(root.contains("Say hello."));
+
+    let command = command_group::run(["say-hello", "--help"])
+        .unwrap()
+        .unwrap();
+    assert!(command.contains("Usage:"));
+    assert!(command.contains("say-hello --name <NAME>"));
+}
+
+#[test]
+fn short_help_shows_summaries_and_long_help_shows_full_documentation() {
+    let root = documented_command_group::run(["-h"]).unwrap().unwrap();
+    assert!(root.contains("Release management."));
+    assert!(!root.contains("Commands for building"));
+    assert!(root.contains("publish     Publish a release.\n"));
+
+    let root = documented_command_group::run(["--help"]).unwrap().unwrap();
+    assert!(root.contains("Release management.\n\nCommands for building and publishing releases."));
+    assert!(root.contains("publish     Publish a release.\n"));
+
+    let short = documented_command_group::run(["publish", "-h"])
+        .unwrap()
+        .unwrap();
+    assert!(short.starts_with("Publish a release.\n\nUsage:"));
+    assert!(short.contains("--version <VERSION>    Release version.\n"));
+    assert!(!short.contains("semantic versioning"));
+
+    let long = documented_command_group::run(["publish", "--help"])
+        .unwrap()
+        .unwrap();
+    assert!(long.starts_with(
+        "Publish a release.\n\nUploads every artifact and then tags the release.\n\n\
+         Tags are never moved once pushed.\n\nUsage:"
+    ));
+    assert!(long.contains("Must follow semantic versioning."));
+}
+
+#[test]
+fn help_renders_markdown_documentation() {
+    let root = documented_command_group::run(["-h"]).unwrap().unwrap();
+    assert!(root.contains("rollback    Roll back to a previous release.\n"));
+
+    let short = documented_command_group::run(["rollback", "-h"])
+        .unwrap()
+        .unwrap();
+    assert!(short.contains("--version <VERSION>    Release to restore, e.g. 1.2.0.\n"));
+    assert!(!short.contains("Examples:"));
+
+    let long = documented_command_group::run(["rollback", "--help"])
+        .unwrap()
+        .unwrap();
+    assert!(long.contains(
+        "Restores the artifacts of version. Read the\n\
+         runbook (https://example.com/runbook) first:\n\n\
+         • notify the on-call engineer\n\
+         • freeze publish\n\nUsage:"
+    ));
+    assert!(long.ends_with("Examples:\n        $ app rollback --version 1.2.0"));
+}
+
+#[test]
+fn help_keeps_brackets_and_underscores_that_are_not_markup() {
+    let long = literal_markup_command::run(["--help"]).unwrap().unwrap();
+    assert!(long.starts_with(
+        "Call a Python hook such as setup or __init__ with care.\n\n\
+         Names match [a-z] or [0-9], as in the guide, and the limit\n\
+         is 2*3*4 [default: 1]; snake_case and very important stay readable.\n\n"
+    ));
+    assert!(long.contains("--pattern <PATTERN>    Pattern like [0-9]+ [default: .*].\n"));
+}
+
+#[test]
+fn color_option_styles_help() {
+    let plain = single_command::run(["--color=never", "--help"])
+        .unwrap()
+        .unwrap();
+    assert!(!plain.contains('\x1b'));
+    assert!(plain.contains("--color <WHEN>"));
+
+    let styled = single_command::run(["--color", "always", "--help"])
+        .unwrap()
+        .unwrap();
+    assert!(styled.contains("\x1b[1;4mUsage:\x1b[0m"));
+    assert!(styled.contains("\x1b[1;36m--name\x1b[0m <NAME>"));
+
+    let root = command_group::run(["--color=always", "-h"])
+        .unwrap()
+        .unwrap();
+    assert!(root.contains("\x1b[1;36msay-hello\x1b[0m"));
+
+    let command = command_group::run(["--color=always", "say-hello", "-h"])
+        .unwrap()
+        .unwrap();
+    assert!(command.contains("\x1b[1;4mOptions:\x1b[0m"));
+
+    let error = single_command::run(["--color=sometimes"]).unwrap_err();
+    assert!(error.starts_with("invalid value for '--color': 'sometimes'"));
+}
+
+#[test]
+fn unknown_names_suggest_similar_ones() {
+    let error = command_group::run(["sya-hello"]).unwrap_err();
+    assert!(error.starts_with(
+        "unknown command 'sya-hello'\n\n  tip: a similar command exists: 'say-hello'\n\nUsage:"
+    ));
+
+    let error = single_command::run(["--aeg", "22", "--name", "John", "--age", "22"]).unwrap_err();
+    assert!(error.starts_with(
+        "unexpected argument '--aeg'\n\n  tip: a similar argument exists: '--age'\n\nUsage:"
+    ));
+
+    let error = command_group::run(["deploy"]).unwrap_err();
+    assert!(!error.contains("tip:"));
 }
 
 #[test]
-fn module_functions_become_kebab_case_subcommands() {
-    command_group::run(["say-hello", "--name", "John"]).unwrap();
-    assert_called("hello:John");
+fn abbrev_accepts_unique_prefixes() {
+    abbreviated_command_group::run(["dep", "--verb", "--ta=prod", "--versi", "2"]).unwrap();
+    assert_called("deploy:prod:Some(\"2\"):true");
 
-    command_group::run(["bye"]).unwrap();
-    assert_called("bye");
+    abbreviated_command_group::run(["st"]).unwrap();
+    assert_called("status");
+
+    let error = abbreviated_command_group::run(["de"]).unwrap_err();
+    assert!(error.starts_with("command 'de' is ambiguous; it could be 'deploy', 'describe'"));
+
+    let error = abbreviated_command_group::run(["deploy", "--ver", "--target", "x"]).unwrap_err();
+    assert!(error.starts_with("option '--ver' is ambiguous; it could be '--verbose', '--version'"));
+
+    let error = command_group::run(["say", "--name", "John"]).unwrap_err();
+    assert!(error.starts_with("unknown command 'say'"));
 }
 
 #[test]
-fn async_function_becomes_cli() {
-    async_single_command::run(["--name", "John"]).unwrap();
-    assert_called("async-hello:John");
+fn repeated_options_are_rejected_unless_overridable() {
+    let error = single_command::run(["--name", "a", "--age", "1", "--name=b"]).unwrap_err();
+    assert!(
+        error.starts_with("option '--name' was given more than once: '--name a' and '--name=b'")
+    );
+
+    let error =
+        single_command::run(["--name", "a", "--age", "1", "--verbose", "--verbose"]).unwrap_err();
+    assert!(error
+        .starts_with("option '--verbose' was given more than once: '--verbose' and '--verbose'"));
+
+    overriding_command::run(["--level", "1", "--force", "--level=3", "--force"]).unwrap();
+    assert_called("configure:3:true");
 }
 
 #[test]
-fn async_module_mixes_async_and_sync_commands() {
-    async_command_group::run(["ping", "--host", "localhost"]).unwrap();
-    assert_called("ping:localhost");
+fn all_argument_problems_are_reported_together() {
+    let error =
+        single_command::run(["--age", "x", "--nickname", "--verbose=yes", "--extra"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 5 problems:\n\
+         \x20 - option '--nickname' requires a value\n\
+         \x20 - invalid value for '--verbose': 'yes' (expected true or false)\n\
+         \x20 - unexpected argument '--extra'\n\
+         \x20 - missing required option '--name'\n\
+         \x20 - invalid value for '--age': 'x' (invalid digit found in string; expected u32)\n\n\
+         Usage:"
+    ));
+    assert_eq!(error.matches("Usage:").count(), 1);
+}
 
-    async_command_group::run(["version"]).unwrap();
-    assert_called("version");
+#[test]
+fn parse_errors_explain_the_expected_value() {
+    let error = typed_command::run(["--port", "70000"]).unwrap_err();
+    assert!(error.starts_with(
+        "invalid value for '--port': '70000' \
+         (number too large to fit in target type; expected u16)"
+    ));
 
-    let error = async_command_group::run(["ping", "--host", "unreachable"]).unwrap_err();
-    assert_eq!(error, "host is unreachable");
+    let error = typed_command::run(["--port", "80", "--level", "low"]).unwrap_err();
+    assert!(error.starts_with("invalid value for '--level': 'low' (expected Level)"));
 }
 
 #[test]
-fn errors_are_descriptive() {
-    let argument_error = single_command::run(["--age", "22"]).unwrap_err();
-    assert!(argument_error.starts_with("missing required option '--name'"));
-    assert!(argument_error.contains("Usage:"));
-    assert!(argument_error.contains("For more information, try '--help'."));
+fn custom_parsers_and_validators() {
+    custom_parsing_command::run(["--port", "8080", "--timeout", "30s"]).unwrap();
+    assert_called("serve:8080:Some(30s)");
 
-    let command_error = command_group::run(["missing"]).unwrap_err();
-    assert!(command_error.starts_with("unknown command 'missing'"));
-    assert!(command_error.contains("Usage:"));
-    assert!(command_error.contains("For more information, try '--help'."));
+    let error = custom_parsing_command::run(["--port", "80", "--timeout", "30"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - invalid value for '--port': '80' (rejected by is_unprivileged)\n\
+         \x20 - invalid value for '--timeout': '30' (missing 's' suffix; expected Duration)\n"
+    ));
+
+    let error = custom_parsing_command::run(["--port", "8080", "--timeout", "90s"]).unwrap_err();
+    assert!(error
+        .starts_with("invalid value for '--timeout': '90s' (timeout must be at most a minute)"));
 }
 
 #[test]
-fn option_is_not_consumed_as_another_options_value() {
-    let error = single_command::run(["--name", "--verbose", "--age", "22"]).unwrap_err();
-    assert!(error.starts_with("option '--name' requires a value"));
-    assert!(error.contains("Usage:"));
+fn human_friendly_values() {
+    human_values_command::run([
+        "--timeout=1m30s",
+        "--max-size",
+        "512MiB",
+        "--mask",
+        "0xff",
+        "--offset=-1_000",
+    ])
+    .unwrap();
+    assert_called("limit:90000:Some(536870912):255:-1000");
+
+    human_values_command::run(["--timeout=1.5s", "--mask=0b1010", "--offset", "0o17"]).unwrap();
+    assert_called("limit:1500:None:10:15");
+
+    let error =
+        human_values_command::run(["--timeout=30", "--mask=0x100", "--offset=1_"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 3 problems:\n\
+         \x20 - invalid value for '--timeout': '30' (missing unit after '30'; expected fire::Duration)\n\
+         \x20 - invalid value for '--mask': '0x100' \
+         (number too large to fit in target type; expected u8)\n\
+         \x20 - invalid value for '--offset': '1_' (invalid digit found in string; expected i64)\n"
+    ));
+
+    let help = human_values_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains("--timeout <TIMEOUT>      Request timeout. (e.g. 30s, 5m, or 1h30m)\n"));
+    assert!(help.contains("--max-size <MAX_SIZE>    (e.g. 512KiB, 64MiB, or 1.5GB)\n"));
 }
 
 #[test]
-fn function_help_uses_signature_and_documentation() {
-    let help = single_command::run(["--help"]).unwrap().unwrap();
-    assert!(help.contains("Greet a person."));
-    assert!(help.contains("Usage:"));
-    assert!(help.contains("--name <NAME>"));
-    assert!(help.contains("Person to greet."));
-    assert!(help.contains("[--nickname <NICKNAME>]"));
-    assert!(help.contains("[--verbose]"));
-    assert!(help.contains("-h, --help"));
+fn input_and_output_files() {
+    let directory = std::env::temp_dir().join(format!("fire-files-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let input = directory.join("input.txt");
+    let output = directory.join("output.txt");
+    std::fs::write(&input, "hello\nworld\n").unwrap();
+
+    file_command::run([
+        "--input",
+        input.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+    ])
+    .unwrap();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "HELLO\nWORLD\n");
+
+    let missing = directory.join("missing.txt");
+    let error =
+        file_command::run(["--input", missing.to_str().unwrap(), "--output", "-"]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "invalid value for '--input': '{0}' (cannot open {0}: ",
+        missing.display()
+    )));
+
+    std::fs::write(&output, "kept\n").unwrap();
+    file_command::run([
+        "--input",
+        missing.to_str().unwrap(),
+        "--output",
+        output.to_str().unwrap(),
+    ])
+    .unwrap_err();
+    assert_eq!(std::fs::read_to_string(&output).unwrap(), "kept\n");
+
+    // An output that cannot be written is reported before the command runs,
+    // and checking a new path does not leave a file behind.
+    let unwritable = directory.join("missing").join("output.txt");
+    let error = file_command::run([
+        "--input",
+        input.to_str().unwrap(),
+        "--output",
+        unwritable.to_str().unwrap(),
+    ])
+    .unwrap_err();
+    assert!(error.starts_with(&format!(
+        "invalid value for '--output': '{0}' (cannot open {0}: ",
+        unwritable.display()
+    )));
+    let fresh = directory.join("fresh.txt");
+    file_command::run([
+        "--input",
+        missing.to_str().unwrap(),
+        "--output",
+        fresh.to_str().unwrap(),
+    ])
+    .unwrap_err();
+    assert!(!fresh.exists());
+
+    let help = file_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(" --input <FILE|-> --output <FILE|->\n"));
+    assert!(help.contains("    --output <FILE|->\n"));
+
+    std::fs::remove_dir_all(&directory).unwrap();
 }
 
 #[test]
-fn command_group_has_root_and_command_help() {
-    let root = command_group::run(["-h"]).unwrap().unwrap();
-    assert!(root.contains("Greeting commands."));
-    assert!(root.contains("say-hello"));
-    assert!(root.contains("Say hello."));
+fn types_named_like_fire_types_parse_through_their_own_from_str() {
+    same_named_command::run(["--input", "-", "--count", "3", "--timeout", "30"]).unwrap();
+    assert_called("tally:-:3:30");
 
-    let command = command_group::run(["say-hello", "--help"])
+    let help = same_named_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(" --input <INPUT> --count <COUNT> --timeout <TIMEOUT>\n"));
+    assert!(!help.contains("(e.g."));
+}
+
+#[cfg(unix)]
+#[test]
+fn os_string_parameters_accept_non_unicode_values() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let raw = OsStr::from_bytes(b"caf\xe9");
+    let mut inline = OsStr::new("--name=").to_os_string();
+    inline.push(raw);
+    path_command::run([OsStr::new("--path"), raw, &inline]).unwrap();
+    assert_called(r#"open:"caf\xE9":Some("caf\xE9"):None"#);
+
+    let error =
+        path_command::run([OsStr::new("--path=a"), OsStr::new("--label"), raw]).unwrap_err();
+    assert!(error.starts_with(
+        "invalid value for '--label': 'caf\u{FFFD}' (not valid UTF-8; expected String)\n"
+    ));
+}
+
+#[test]
+fn borrowed_and_repeated_parameters() {
+    borrowed_command::run([
+        "--root=/srv",
+        "--include",
+        "src",
+        "--exclude=target",
+        "--include=docs",
+        "--level",
+        "3",
+    ])
+    .unwrap();
+    assert_called(r#"archive:/srv:None:["src", "docs"]:["target"]:[3]"#);
+
+    borrowed_command::run(["--root", ".", "--label", "nightly"]).unwrap();
+    assert_called(r#"archive:.:Some("nightly"):[]:[]:[]"#);
+
+    let error =
+        borrowed_command::run(["--root=.", "--level=1", "--level=x", "--level=300"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - invalid value for '--level': 'x' (invalid digit found in string; expected u8)\n\
+         \x20 - invalid value for '--level': '300' \
+         (number too large to fit in target type; expected u8)\n"
+    ));
+
+    let help = borrowed_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(
+        " --root <ROOT> [--label <LABEL>] [--include <INCLUDE>]... [--exclude <EXCLUDE>]... \
+         [--level <LEVEL>]...\n"
+    ));
+    assert!(help.contains("    --include <INCLUDE>    Paths to include.\n"));
+}
+
+#[test]
+fn negatable_flags() {
+    negatable_command::run(Vec::<String>::new()).unwrap();
+    assert_called("build:true:false:None");
+
+    negatable_command::run(["--no-cache", "--release=true", "--color"]).unwrap();
+    assert_called("build:false:true:Some(true)");
+
+    negatable_command::run(["--cache=false", "--release=false", "--no-color"]).unwrap();
+    assert_called("build:false:false:Some(false)");
+
+    let error = negatable_command::run(["--cache", "--no-cache", "--no-release"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - option '--cache' was given more than once: '--cache' and '--no-cache'\n\
+         \x20 - unexpected argument '--no-release'\n"
+    ));
+
+    let help = negatable_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(" [--[no-]cache] [--release] [--[no-]color]\n"));
+    assert!(help.contains("    --[no-]cache    Reuse earlier results.\n"));
+    assert!(help.contains("    --[no-]color    Colorize compiler output.\n"));
+}
+
+#[test]
+fn counted_flags() {
+    counting_command::run(Vec::<String>::new()).unwrap();
+    assert_called("log:0:0:None");
+
+    counting_command::run(["-vvv"]).unwrap();
+    assert_called("log:3:0:None");
+
+    counting_command::run(["-v", "--verbose", "--name", "x", "-qv", "--quiet"]).unwrap();
+    assert_called(r#"log:3:2:Some("x")"#);
+
+    let error = counting_command::run(["-vx", "--verbose=2"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - unexpected argument '-vx'\n\
+         \x20 - flag '--verbose' does not take a value\n"
+    ));
+
+    let help = counting_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(" [-v]... [-q]... [--name <NAME>]\n"));
+    assert!(help.contains("    -v, --verbose     More output; repeat for even more.\n"));
+    assert!(help.contains("    -q, --quiet\n"));
+}
+
+#[test]
+fn related_and_grouped_parameters() {
+    related_command::run(["--csv", "--delimiter=;", "--stdout", "--include=a"]).unwrap();
+    assert_called(r#"export:false:true:Some(';'):None:true:["a"]:[]:false"#);
+
+    related_command::run(["--json", "--output", "out.json"]).unwrap();
+    assert_called(r#"export:true:false:None:Some("out.json"):false:[]:[]:false"#);
+
+    // A flag turned off does not count as given.
+    related_command::run([
+        "--json",
+        "--csv=false",
+        "--output=x",
+        "--stdout=false",
+        "--header=false",
+    ])
+    .unwrap();
+    assert_called(r#"export:true:false:None:Some("x"):false:[]:[]:false"#);
+
+    let error = related_command::run(["--json", "--header"]).unwrap_err();
+    assert!(error.starts_with("option '--header' requires '--csv'\n"));
+
+    let error = related_command::run(["--delimiter=,"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - option '--delimiter' requires '--csv'\n\
+         \x20 - one of '--json' or '--csv' is required\n"
+    ));
+
+    let error = related_command::run(["--json", "--csv", "--output=x", "--stdout"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - option '--stdout' cannot be used with '--output'\n\
+         \x20 - option '--json' cannot be used with '--csv'\n"
+    ));
+
+    let help = related_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(
+        " (--json | --csv) [--delimiter <DELIMITER>] [--output <OUTPUT>] [--stdout] \
+         [--include <INCLUDE> | --exclude <EXCLUDE>]... [--header]\n"
+    ));
+}
+
+#[test]
+fn map_and_tuple_parameters() {
+    structured_command::run([
+        "--point=1,-2",
+        "--label",
+        "team=core",
+        "--label=env=prod",
+        "--limit=cpu=4",
+        "--limit=mem=0x10",
+        "--range=1,2,x",
+    ])
+    .unwrap();
+    assert_called(
+        r#"plot:(1, -2):{"env": "prod", "team": "core"}:[("cpu", 4), ("mem", 16)]:Some((1, 2, "x"))"#,
+    );
+
+    let error = structured_command::run([
+        "--point=1",
+        "--label=env",
+        "--limit=cpu=lots",
+        "--range=1,x,y",
+    ])
+    .unwrap_err();
+    assert!(error.starts_with(
+        "found 4 problems:\n\
+         \x20 - invalid value for '--point': '1' (expected 2 comma-separated values)\n\
+         \x20 - invalid value for '--label': 'env' (expected KEY=VALUE)\n\
+         \x20 - invalid value for '--limit': 'cpu=lots' (invalid digit found in string; expected u8)\n\
+         \x20 - invalid value for '--range': '1,x,y' (invalid digit found in string; expected u8)\n"
+    ));
+
+    let error =
+        structured_command::run(["--point=1,2", "--label=env=a", "--label", "env=b"]).unwrap_err();
+    assert!(error.starts_with("option '--label' was given key 'env' more than once\n"));
+
+    let help = structured_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(
+        " --point <VALUE,VALUE> [--label <KEY=VALUE>]... [--limit <KEY=VALUE>]... \
+         [--range <VALUE,VALUE,VALUE>]\n"
+    ));
+    assert!(help.contains("    --label <KEY=VALUE>            Labels to attach.\n"));
+}
+
+#[test]
+fn options_with_optional_values() {
+    optional_value_command::run(Vec::<String>::new()).unwrap();
+    assert_called("serve:None:None:None");
+
+    optional_value_command::run(["--log", "--name", "web", "--level=3"]).unwrap();
+    assert_called(r#"serve:Some(None):Some(Some(3)):Some("web")"#);
+
+    optional_value_command::run(["--log=out.log", "--level"]).unwrap();
+    assert_called(r#"serve:Some(Some("out.log")):Some(None):None"#);
+
+    let error = optional_value_command::run(["--log", "out.log", "--level=x"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - unexpected argument 'out.log'\n\
+         \x20 - invalid value for '--level': 'x' (invalid digit found in string; expected u8)\n"
+    ));
+
+    let help = optional_value_command::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(" [--log[=<LOG>]] [--level[=<LEVEL>]] [--name <NAME>]\n"));
+    assert!(
+        help.contains("    --log[=<LOG>]        Write a log, to the given file or the default one.\n")
+    );
+}
+
+#[test]
+fn response_files_expand_into_arguments() {
+    let directory = std::env::temp_dir().join(format!("fire-response-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let ids = directory.join("ids.txt");
+    let command = directory.join("command.txt");
+    let nested = directory.join("nested.txt");
+    std::fs::write(&ids, "# generated\n--ids 1\n--ids=2 --ids '3'\n").unwrap();
+    std::fs::write(
+        &command,
+        format!("tag @{} --name \"two words\"\n", ids.display()),
+    )
+    .unwrap();
+    std::fs::write(&nested, format!("@{}\n", nested.display())).unwrap();
+    let at = |path: &std::path::Path| format!("@{}", path.display());
+
+    response_file_command_group::run(["tag", &at(&ids), "--ids", "4"]).unwrap();
+    assert_called("tag:[1, 2, 3, 4]:None");
+
+    response_file_command_group::run([at(&command)]).unwrap();
+    assert_called(r#"tag:[1, 2, 3]:Some("two words")"#);
+
+    response_file_command_group::run(["mention", "--handle", &at(&ids)]).unwrap();
+    assert_called(&format!("mention:{}", at(&ids)));
+
+    let error = response_file_command_group::run(["tag", &at(&nested)]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "response file '{}' is nested more than 10 levels deep",
+        nested.display()
+    )));
+
+    let missing = directory.join("missing.txt");
+    let error = response_file_command_group::run(["tag", &at(&missing)]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "cannot read response file '{}': ",
+        missing.display()
+    )));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn response_files_may_hold_non_unicode_arguments() {
+    let directory = std::env::temp_dir().join(format!("fire-raw-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let arguments = directory.join("arguments.txt");
+    std::fs::write(&arguments, b"--path 'caf\xe9' --name=caf\xe9\n").unwrap();
+
+    path_command::run([format!("@{}", arguments.display())]).unwrap();
+    assert_called(r#"open:"caf\xE9":Some("caf\xE9"):None"#);
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn options_fall_back_to_environment_and_config_file() {
+    let directory = std::env::temp_dir().join(format!("fire-config-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let toml = directory.join("app.toml");
+    let json = directory.join("app.json");
+    std::fs::write(
+        &toml,
+        "port = 80 # shared\n\n\
+         [deploy]\ntarget = \"staging\"\ntags = [\"a\", \"b\"]\nforce = true\n",
+    )
+    .unwrap();
+    std::fs::write(
+        &json,
+        r#"{"port": 8080, "deploy": {"tags": "c", "force": null}}"#,
+    )
+    .unwrap();
+    let config = |path: &std::path::Path| format!("--config={}", path.display());
+
+    configured_command_group::run([config(&toml), "deploy".to_string()]).unwrap();
+    assert_called(r#"deploy:staging:Some(80):["a", "b"]:true"#);
+
+    configured_command_group::run(["deploy", "--tags=x", "--target", "prod", &config(&toml)])
+        .unwrap();
+    assert_called(r#"deploy:prod:Some(80):["x"]:true"#);
+
+    configured_command_group::run(["deploy", "--target=prod", &config(&json)]).unwrap();
+    assert_called(r#"deploy:prod:Some(8080):["c"]:false"#);
+
+    std::fs::write(&toml, "[deploy]\nforce = \"maybe\"\nverbose = true\n").unwrap();
+    let error =
+        configured_command_group::run(["deploy", "--target=x", &config(&toml)]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "found 2 problems:\n\
+         \x20 - unknown option 'verbose' in config file '{0}'\n\
+         \x20 - invalid value for '--force' from config file '{0}' (expected true or false)\n",
+        toml.display()
+    )));
+
+    // A misspelled command table is reported rather than silently ignored.
+    std::fs::write(&toml, "[deplyo]\ntarget = \"x\"\n\n[deploy]\nprot = 1\n").unwrap();
+    let error = configured_command_group::run(["deploy", &config(&toml)]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "found 3 problems:\n\
+         \x20 - unknown command 'deplyo' in config file '{0}'\n\
+         \x20     tip: a similar command exists: 'deploy'\n\
+         \x20 - unknown option 'prot' in config file '{0}'\n\
+         \x20     tip: a similar option exists: 'port'\n\
+         \x20 - missing required option '--target'\n",
+        toml.display()
+    )));
+
+    std::fs::write(&toml, "port = [1\n").unwrap();
+    let error = configured_command_group::run(["deploy", &config(&toml)]).unwrap_err();
+    assert!(error.starts_with(&format!(
+        "invalid config file '{}': expected ',' or ']' in array on line 1",
+        toml.display()
+    )));
+
+    let help = configured_command_group::run(["deploy", "-h"])
         .unwrap()
         .unwrap();
-    assert!(command.contains("Usage:"));
-    assert!(command.contains("say-hello --name <NAME>"));
+    assert!(help.contains("    --target <TARGET>      [env: FIRE_TESTS_DEPLOY_TARGET]\n"));
+    assert!(help.contains("    --config <PATH>        Read option defaults from this file\n"));
+    assert!(help.contains("    --fire-print-config    Show where option values come from"));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn options_fall_back_to_environment_variables() {
+    // Changing the environment while other tests run is unsound, so the test
+    // runs itself again in a process that starts with the variable set.
+    if std::env::var_os("FIRE_TESTS_DEPLOY_TARGET").is_none() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["options_fall_back_to_environment_variables", "--exact"])
+            .env("FIRE_TESTS_DEPLOY_TARGET", "from-env")
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        return;
+    }
+
+    let directory = std::env::temp_dir().join(format!("fire-env-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let toml = directory.join("app.toml");
+    std::fs::write(&toml, "[deploy]\ntarget = \"staging\"\nforce = true\n").unwrap();
+    let config = format!("--config={}", toml.display());
+
+    configured_command_group::run(["deploy", &config]).unwrap();
+    assert_called("deploy:from-env:None:[]:true");
+
+    configured_command_group::run(["deploy", "--target=prod", &config]).unwrap();
+    assert_called("deploy:prod:None:[]:true");
+
+    let report = configured_command_group::run([
+        "deploy",
+        "--port=1",
+        "--token=hunter2",
+        "--fire-print-config",
+    ])
+    .unwrap()
+    .unwrap();
+    assert_eq!(
+        report,
+        "--target = from-env  # environment variable FIRE_TESTS_DEPLOY_TARGET\n\
+         --port = 1  # command line\n\
+         --tags is not set\n\
+         --force = false  # default\n\
+         --token = <redacted>  # command line"
+    );
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn command_line_options_replace_conflicting_config_values() {
+    let directory = std::env::temp_dir().join(format!("fire-relations-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let toml = directory.join("app.toml");
+    std::fs::write(
+        &toml,
+        "[export]\njson = true\noutput = \"out.txt\"\ndelimiter = \";\"\n",
+    )
+    .unwrap();
+    let config = format!("--config={}", toml.display());
+
+    configured_command_group::run(["export", &config]).unwrap();
+    assert_called(r#"export:true:false:Some("out.txt"):false:Some(';')"#);
+
+    configured_command_group::run(["export", "--csv", "--stdout", &config]).unwrap();
+    assert_called("export:false:true:None:true:Some(';')");
+
+    let report = configured_command_group::run([
+        "export",
+        "--csv",
+        "--stdout",
+        "--fire-print-config",
+        &config,
+    ])
+    .unwrap()
+    .unwrap();
+    assert!(report.starts_with(&format!(
+        "--json = false  # default\n\
+         --csv = true  # command line\n\
+         --output is not set\n\
+         --stdout = true  # command line\n\
+         --delimiter = ;  # config file '{}'",
+        toml.display()
+    )));
+
+    let error = configured_command_group::run(["export", "--json", "--csv", &config]).unwrap_err();
+    assert!(error.starts_with("option '--json' cannot be used with '--csv'\n"));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn config_files_reject_what_they_do_not_support() {
+    let directory = std::env::temp_dir().join(format!("fire-syntax-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let problem = |name: &str, contents: &str| {
+        let path = directory.join(name);
+        std::fs::write(&path, contents).unwrap();
+        let config = format!("--config={}", path.display());
+        let error = configured_command_group::run(["deploy", "--target=x", &config]).unwrap_err();
+        let prefix = format!("invalid config file '{}': ", path.display());
+        error
+            .lines()
+            .next()
+            .unwrap()
+            .strip_prefix(&prefix)
+            .unwrap()
+            .to_string()
+    };
+
+    assert_eq!(
+        problem("a.toml", "[deploy]\ntarget = staging\n"),
+        "expected a value, not 'staging'; strings must be quoted on line 2"
+    );
+    assert_eq!(
+        problem("a.toml", "target = \"\"\"\nstaging\"\"\"\n"),
+        "multi-line strings are not supported on line 1"
+    );
+    assert_eq!(
+        problem("a.toml", "deploy.target = \"x\"\n"),
+        "dotted keys are not supported on line 1"
+    );
+    assert_eq!(
+        problem("a.toml", "[deploy.eu]\n"),
+        "nested tables are not supported on line 1"
+    );
+    assert_eq!(
+        problem(
+            "a.toml",
+            "[deploy]\nforce = true\n[deploy]\nforce = false\n"
+        ),
+        "'deploy' is defined twice on line 3"
+    );
+    assert_eq!(
+        problem("a.toml", "port = 08\n"),
+        "expected a value, not '08'; strings must be quoted on line 1"
+    );
+    assert_eq!(
+        problem("a.json", r#"{"port": 1, "port": 2}"#),
+        "\"port\" is defined twice on line 1"
+    );
+    assert_eq!(
+        problem("a.json", r#"{"target": "\ud83d"}"#),
+        "unpaired surrogate '\\ud83d' on line 1"
+    );
+
+    let path = directory.join("b.json");
+    std::fs::write(
+        &path,
+        r#"{"deploy": {"tags": ["\ud83d\ude00"], "port": 443}}"#,
+    )
+    .unwrap();
+    let config = format!("--config={}", path.display());
+    configured_command_group::run(["deploy", "--target=x", &config]).unwrap();
+    assert_called("deploy:x:Some(443):[\"\u{1F600}\"]:false");
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn prompting_is_skipped_when_the_command_line_is_already_wrong() {
+    prompting_command::run(["--cluster=us", "--release", "1.2"]).unwrap();
+    assert_called("roll-out:us:1.2");
+
+    // Asking would be pointless, so the command line is reported as usual
+    // even on a terminal.
+    let error = prompting_command::run(["--cluster=mars", "--force"]).unwrap_err();
+    assert!(error.starts_with(
+        "found 3 problems:\n\
+         \x20 - unexpected argument '--force'\n\
+         \x20 - invalid value for '--cluster': 'mars' (unknown cluster mars; expected Cluster)\n\
+         \x20 - missing required option '--release'\n"
+    ));
+}
+
+#[test]
+fn secret_values_are_redacted() {
+    secret_command::run(["--key", "sk-123", "--pin=42"]).unwrap();
+    assert_called("connect:sk-123:Some(42):None:[]");
+
+    let error = secret_command::run(["--key", "hunter2", "--pin", "4x2", "--pin=1"]).unwrap_err();
+    assert!(!error.contains("hunter2"));
+    assert!(!error.contains("4x2"));
+    assert!(error.starts_with(
+        "found 3 problems:\n\
+         \x20 - option '--pin' was given more than once: '--pin <redacted>' and '--pin=<redacted>'\n\
+         \x20 - invalid value for '--key': <redacted> (expected String)\n\
+         \x20 - invalid value for '--pin': <redacted> (expected u16)\n"
+    ));
+
+    // A short value must not be replaced inside the rest of the message.
+    let error = secret_command::run(["--key=sk-1", "--pin", "a"]).unwrap_err();
+    assert!(error.starts_with("invalid value for '--pin': <redacted> (expected u16)\n"));
+
+    let error = secret_command::run([
+        "--key=sk-1",
+        "--login=admin,hunter2",
+        "--header",
+        "auth=swordfish",
+        "--header=auth=t-1",
+    ])
+    .unwrap_err();
+    assert!(!error.contains("admin"));
+    assert!(!error.contains("hunter2"));
+    assert!(!error.contains("auth"));
+    assert!(!error.contains("swordfish"));
+    assert!(error.starts_with(
+        "found 2 problems:\n\
+         \x20 - invalid value for '--login': <redacted> (expected (String, Token))\n\
+         \x20 - invalid value for '--header': <redacted> (expected (String, Token))\n"
+    ));
+}
+
+#[test]
+fn choices_are_taken_by_number_or_name() {
+    let ask = |input: &[u8]| {
+        let mut output = Vec::new();
+        let answer = fire::__private::ask(
+            &mut &input[..],
+            &mut output,
+            "Cluster to deploy to.",
+            Some(&["eu", "us"]),
+            false,
+            |_| Ok(()),
+        );
+        (answer, String::from_utf8(output).unwrap())
+    };
+    let menu = "Cluster to deploy to:\n  1) eu\n  2) us\nChoose 1-2: ";
+
+    assert_eq!(ask(b"2\n"), (Some("us".to_string()), menu.to_string()));
+    assert_eq!(ask(b" eu \n"), (Some("eu".to_string()), menu.to_string()));
+
+    let (answer, output) = ask(b"0\n3\nasia\n1\n");
+    assert_eq!(answer.as_deref(), Some("eu"));
+    assert_eq!(
+        output,
+        format!(
+            "{menu}'0' is not one of the choices\n\
+             {menu}'3' is not one of the choices\n\
+             {menu}'asia' is not one of the choices\n\
+             {menu}"
+        )
+    );
+
+    assert_eq!(ask(b""), (None, format!("{menu}\n")));
+    assert_eq!(ask(b"5\n").0, None);
+}
+
+#[test]
+fn answers_that_fail_the_check_are_asked_for_again() {
+    let mut output = Vec::new();
+    let mut checked = Vec::new();
+    let answer = fire::__private::ask(
+        &mut &b"\nabc\n8080\n"[..],
+        &mut output,
+        "Port:",
+        None,
+        false,
+        |answer| {
+            checked.push(answer.to_string());
+            answer.parse::<u16>().map(|_| ()).map_err(|error| error.to_string())
+        },
+    );
+    assert_eq!(answer.as_deref(), Some("8080"));
+    assert_eq!(checked, ["abc", "8080"]);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Port: Port: invalid digit found in string\nPort: "
+    );
+}
+
+#[test]
+fn secret_answers_are_taken_as_typed() {
+    let mut output = Vec::new();
+    let answer = fire::__private::ask(
+        &mut &b"\n  hunter2 \n"[..],
+        &mut output,
+        "Password.",
+        None,
+        true,
+        |_| Ok(()),
+    );
+    assert_eq!(answer.as_deref(), Some("  hunter2 "));
+    // The newline typed after each answer is not echoed, so it is written.
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "Password: \nPassword: \n"
+    );
+}
+
+#[test]
+fn confirmation_is_skipped_with_yes() {
+    confirmed_command_group::run(["drop-database", "--yes", "--name", "test"]).unwrap();
+    assert_called("drop-database:test");
+
+    confirmed_command_group::run(["drop-database", "--name=staging", "-y"]).unwrap();
+    assert_called("drop-database:staging");
+
+    let error =
+        confirmed_command_group::run(["drop-database", "--yes=true", "--name", "x"]).unwrap_err();
+    assert!(error.starts_with("flag '--yes' does not take a value\n"));
+
+    let help = confirmed_command_group::run(["drop-database", "-h"])
+        .unwrap()
+        .unwrap();
+    assert!(help.contains(" drop-database --name <NAME> [--yes]\n"));
+    assert!(help.contains("    -y, --yes         Run without asking for confirmation\n"));
+}
+
+#[test]
+fn confirmation_takes_only_yes() {
+    let confirm = |input: &[u8]| {
+        let mut output = Vec::new();
+        let agreed = fire::__private::confirm_with(&mut &input[..], &mut output, "Continue? ");
+        (agreed, String::from_utf8(output).unwrap())
+    };
+    for answer in ["y\n", "yes\n", " YES \r\n", "Y"] {
+        assert_eq!(confirm(answer.as_bytes()), (true, "Continue? [y/N] ".to_string()));
+    }
+    for answer in ["n\n", "\n", "yep\n", "y es\n"] {
+        assert_eq!(confirm(answer.as_bytes()), (false, "Continue? [y/N] ".to_string()));
+    }
+    assert_eq!(confirm(b""), (false, "Continue? [y/N] \n".to_string()));
+}
+
+#[test]
+fn confirmation_comes_before_values_are_converted() {
+    // Whether the question is asked depends on standard input being a
+    // terminal, so the test runs itself again in a process without one.
+    if std::env::var_os("FIRE_TESTS_WITHOUT_TERMINAL").is_none() {
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["confirmation_comes_before_values_are_converted", "--exact"])
+            .env("FIRE_TESTS_WITHOUT_TERMINAL", "1")
+            .stdin(std::process::Stdio::null())
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stdout)
+        );
+        return;
+    }
+
+    let error = confirmed_command_group::run(["restore", "--from", "monday", "--keep", "1"])
+        .unwrap_err();
+    assert!(error.starts_with("this command asks for confirmation; pass '--yes' to run it"));
+    let calls = CALLS.lock().unwrap();
+    assert!(!calls.iter().any(|call| call == "parsed-backup:monday"));
+    drop(calls);
+
+    // An incomplete command line is reported as such, without the question.
+    let error = confirmed_command_group::run(["restore", "--from", "tuesday"]).unwrap_err();
+    assert!(error.starts_with("missing required option '--keep'\n"));
+    assert_called("parsed-backup:tuesday");
+
+    confirmed_command_group::run(["restore", "--from", "sunday", "--keep", "2", "-y"]).unwrap();
+    assert_called("parsed-backup:sunday");
+    assert_called("restore:sunday:2");
+}
+
+#[test]
+fn shell_sessions_run_each_line() {
+    let session = |script: &[u8], interactive: bool| {
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        let result = fire::__private::run_shell(
+            &mut &script[..],
+            &mut output,
+            &mut errors,
+            interactive,
+            "app",
+            shell_command_group::run,
+        );
+        assert_eq!(result, Ok(None));
+        (
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(errors).unwrap(),
+        )
+    };
+
+    let (output, errors) = session(
+        b"scale --replicas 3\n\nscale --replicas many\nshell\n--color=never --fire-batch -\n\
+          scale --replicas 4\nhistory\n",
+        false,
+    );
+    assert_called("scale:3");
+    assert_called("scale:4");
+    assert_eq!(
+        output,
+        "    1  scale --replicas 3\n    2  scale --replicas many\n    3  shell\n    \
+         4  --color=never --fire-batch -\n    5  scale --replicas 4\n    6  history\n"
+    );
+    assert!(errors.starts_with("error: invalid value for '--replicas': 'many' ("));
+    assert!(errors.ends_with(
+        "error: the shell is already running\nerror: a batch cannot run inside the shell\n"
+    ));
+
+    // Nothing after `exit` or `quit` runs.
+    for last in ["exit", "quit"] {
+        let script = format!("status\n{last}\nscale --replicas 9\n");
+        let (output, errors) = session(script.as_bytes(), true);
+        assert_called("status");
+        assert_eq!(output, "app> app> ");
+        assert_eq!(errors, "");
+    }
+    assert!(!CALLS.lock().unwrap().iter().any(|call| call == "scale:9"));
+
+    // The prompt ends with a newline when the input does.
+    assert_eq!(session(b"", true), ("app> \n".to_string(), String::new()));
+
+    let (output, _) = session(b"help\n", false);
+    assert!(output.contains("Commands:\n    status    Show the state of the cluster.\n"));
+    assert!(output.ends_with("\n\nIn the shell, also: help [COMMAND], history, exit\n"));
+
+    let (output, _) = session(b"help scale\n", false);
+    assert!(output.contains(" scale --replicas <REPLICAS>\n"));
+}
+
+#[test]
+fn repl_adds_a_shell_command() {
+    shell_command_group::run(["status"]).unwrap();
+    assert_called("status");
+
+    let help = shell_command_group::run(["-h"]).unwrap().unwrap();
+    assert!(help.contains(
+        "Commands:\n    status    Show the state of the cluster.\n    \
+         scale     Change the number of replicas.\n    \
+         shell     Start an interactive shell\n"
+    ));
+
+    let error = shell_command_group::run(["shell", "status"]).unwrap_err();
+    assert!(error.starts_with("unexpected argument 'status'\n"));
+
+    let error = shell_command_group::run(["shel"]).unwrap_err();
+    assert!(
+        error.starts_with("unknown command 'shel'\n\n  tip: a similar command exists: 'shell'\n")
+    );
+}
+
+#[test]
+fn batch_files_run_one_command_per_line() {
+    let directory = std::env::temp_dir().join(format!("fire-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let script = directory.join("provision.txt");
+    std::fs::write(
+        &script,
+        "# provisioning\n\
+         tag --ids 10 --name \"first run\"\n\
+         tag --ids x\n\
+         \n\
+         set -e\n\
+         mention --handle ops\n\
+         untag --ids 1\n\
+         mention --handle never\n",
+    )
+    .unwrap();
+    let batch = format!("--fire-batch={}", script.display());
+
+    let error = response_file_command_group::run([batch.as_str()]).unwrap_err();
+    assert_called(r#"tag:[10]:Some("first run")"#);
+    assert_called("mention:ops");
+    assert!(error.starts_with("line 7: unknown command 'untag'\n"));
+    assert!(!CALLS.lock().unwrap().contains(&"mention:never".to_string()));
+
+    std::fs::write(&script, "tag --ids 11\ntag --ids=y\nmention --handle ok\n").unwrap();
+    let error =
+        response_file_command_group::run(["--fire-batch", script.to_str().unwrap()]).unwrap_err();
+    assert_called("tag:[11]:None");
+    assert_called("mention:ok");
+    assert_eq!(error, "1 of 3 commands failed");
+
+    let error = response_file_command_group::run([batch.as_str(), "tag"]).unwrap_err();
+    assert!(error.starts_with("unexpected argument 'tag'\n"));
+
+    let help = response_file_command_group::run(["--help"]).unwrap().unwrap();
+    assert!(help.contains(
+        "    --fire-batch <FILE|->    Run each line of this file as a command\n    \
+         -h, --help               Print help"
+    ));
+
+    std::fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn batches_stop_at_failures_only_after_set_e() {
+    let batch = |script: &str| {
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        let result = fire::__private::run_batch(
+            &mut script.as_bytes(),
+            &mut output,
+            &mut errors,
+            response_file_command_group::run,
+        );
+        (
+            result,
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(errors).unwrap(),
+        )
+    };
+
+    // Comments and blank lines are skipped but still counted, and lines may
+    // end with "\r\n".
+    let (result, output, errors) = batch(
+        "# setup\r\n\
+         mention --handle a\r\n\
+         \r\n\
+         mention\r\n\
+         mention --handle b # trailing\r\n\
+         archive -h\r\n",
+    );
+    assert_eq!(result, Err("1 of 4 commands failed".to_string()));
+    assert_called("mention:a");
+    assert_called("mention:b");
+    assert!(errors.starts_with("error: line 4: missing required option '--handle'\n"));
+    assert!(output.starts_with("Usage: "));
+    assert!(output.contains(" archive --path <PATH>\n"));
+
+    let (result, _, errors) = batch(
+        "set -e\n\
+         set +e\n\
+         mention --handle=c --handle=d\n\
+         set -e\n\
+         mention --handle e\n\
+         untag\n\
+         mention --handle never-run\n",
+    );
+    let error = result.unwrap_err();
+    assert!(errors.starts_with("error: line 3: option '--handle' "));
+    assert!(error.starts_with("line 6: unknown command 'untag'\n"));
+    assert_called("mention:e");
+    assert!(!CALLS.lock().unwrap().iter().any(|call| call == "mention:never-run"));
+
+    let (result, _, _) = batch("mention --handle 'open\n");
+    assert_eq!(result, Err("line 1: unterminated single quote".to_string()));
+    let (result, _, _) = batch("\n--fire-batch=-\n");
+    assert_eq!(
+        result,
+        Err("line 2: a batch cannot start another batch".to_string())
+    );
+    assert_eq!(batch(""), (Ok(None), String::new(), String::new()));
+}
+
+#[cfg(unix)]
+#[test]
+fn batch_files_may_hold_non_unicode_arguments() {
+    let directory = std::env::temp_dir().join(format!("fire-raw-batch-{}", std::process::id()));
+    std::fs::create_dir_all(&directory).unwrap();
+    let script = directory.join("archive.txt");
+    std::fs::write(&script, b"# caf\xe9\r\narchive --path 'caf\xe9'\r\n").unwrap();
+
+    response_file_command_group::run(["--fire-batch", script.to_str().unwrap()]).unwrap();
+    assert_called(r#"archive:"caf\xE9""#);
+
+    std::fs::remove_dir_all(&directory).unwrap();
 }
+